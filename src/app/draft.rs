@@ -0,0 +1,83 @@
+use crate::hackernews::session::Action;
+
+/// Something being written to post with the account, kept between rounds of
+/// editing so it can be previewed and edited again before it's posted
+#[derive(Clone, Debug)]
+pub enum Draft {
+    Reply {
+        parent: i32,
+
+        /// Title of the story or author of the comment being replied to
+        to: String,
+        text: String
+    },
+    Submission {
+        title: String,
+        url: String,
+        text: String
+    }
+}
+
+impl Draft {
+    pub fn reply(parent: i32, to: &str) -> Draft {
+        Draft::Reply {
+            parent,
+            to: to.to_string(),
+            text: String::new()
+        }
+    }
+
+    pub fn submission() -> Draft {
+        Draft::Submission {
+            title: String::new(),
+            url: String::new(),
+            text: String::new()
+        }
+    }
+
+    /// Contents of the file handed to the editor. Submissions have their title
+    /// and url on the first two lines, above the text
+    pub fn editor_text(&self) -> String {
+        match self {
+            Draft::Reply { text, .. } => text.clone(),
+            Draft::Submission { title, url, text } => format!("Title: {}\nURL: {}\n\n{}", title, url, text)
+        }
+    }
+
+    /// Takes the contents of the file back from the editor
+    pub fn apply_edit(&mut self, contents: &str) {
+        match self {
+            Draft::Reply { text, .. } => *text = contents.trim().to_string(),
+            Draft::Submission { title, url, text } => {
+                // Only the lines at the top are headers, so the text itself
+                // can mention a title or url
+                let mut lines = contents.lines().peekable();
+                while let Some(line) = lines.peek() {
+                    if let Some(value) = line.strip_prefix("Title:") {
+                        *title = value.trim().to_string();
+                    } else if let Some(value) = line.strip_prefix("URL:") {
+                        *url = value.trim().to_string();
+                    } else {
+                        break;
+                    }
+                    lines.next();
+                }
+                *text = lines.collect::<Vec<&str>>().join("\n").trim().to_string();
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Draft::Reply { text, .. } => text.is_empty(),
+            Draft::Submission { title, .. } => title.is_empty()
+        }
+    }
+
+    pub fn action(&self) -> Action {
+        match self.clone() {
+            Draft::Reply { parent, text, .. } => Action::Reply { parent, text },
+            Draft::Submission { title, url, text } => Action::Submit { title, url, text }
+        }
+    }
+}
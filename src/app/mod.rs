@@ -0,0 +1,2056 @@
+pub mod draft;
+
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    sync::mpsc,
+    time::{Duration, Instant}
+};
+
+use termion::event::Key;
+use tokio::task::JoinHandle;
+
+use crate::{
+    config::Config,
+    hackernews::{
+        self,
+        comments::{self, Comment, SortOrder},
+        polls::PollOption,
+        session::{Action, LoggedOut, Session},
+        stories::{Feed, Story},
+        stream::StreamEvent,
+        users::User
+    },
+    reader::{self, Article},
+    storage::{
+        self,
+        bookmarks::{self, Bookmark, BookmarkKind},
+        favorites::{self, Favorites},
+        history::{self, HistoryEntry, HistoryFilter},
+        session::SavedSession
+    },
+    util::{event::Event, prompt::Prompt, StatefulList}
+};
+
+use self::draft::Draft;
+
+/// Stories fetched at a time when paging through a feed
+pub const PAGE_SIZE: usize = 25;
+
+/// How close to the last loaded story the selection gets before the next
+/// page is loaded
+const LOAD_AHEAD: usize = 5;
+
+/// How long the selection stays on a story before its preview is fetched, so
+/// that scrolling through the list doesn't fetch every story on the way
+const PREVIEW_DELAY: Duration = Duration::from_millis(300);
+
+/// Top level comments fetched for the preview of a story
+const PREVIEW_COMMENTS: usize = 5;
+
+/// Columns that the terminal needs for the split layout to be used
+const MIN_SPLIT_WIDTH: u16 = 100;
+
+/// How long changes pushed over a subscription are gathered before the view
+/// is refreshed, so that a busy thread isn't refetched on every change
+const STREAM_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Results of background work, delivered through the event queue
+pub enum Message {
+    StoriesRefreshed {
+        feed: Feed,
+        ids: Vec<u32>,
+        stories: Vec<Story>
+    },
+    /// A page of stories that continues the feed from `start`
+    StoriesPage {
+        feed: Feed,
+        start: usize,
+        stories: Result<Vec<Story>, String>
+    },
+    CommentsRefreshed {
+        root: i32,
+        comments: Vec<Comment>
+    },
+    /// Replies to `parent` in the thread rooted at `root`, loaded in the
+    /// background after the thread was opened
+    RepliesLoaded {
+        root: i32,
+        parent: i32,
+        replies: Vec<Comment>
+    },
+    /// Items that changed since the last poll of the updates endpoint, along
+    /// with the id of the newest item on the site
+    ItemsChanged {
+        items: Vec<i32>,
+        max_item: i32
+    },
+    /// A change pushed by the server over a streaming subscription to `path`
+    Streamed {
+        path: String,
+        event: StreamEvent
+    },
+    /// The subscription to a path ended, either by the server or an error
+    StreamClosed(String),
+    /// Top comments of a story for its preview in the split layout
+    PreviewLoaded {
+        story: i32,
+        comments: Vec<Comment>
+    },
+    RefreshFailed(String)
+}
+
+/// A list of stories, either a feed or the results of a search
+pub struct StoriesView {
+    pub title: String,
+
+    /// Feed the stories come from, none for search results
+    pub feed: Option<Feed>,
+
+    /// Ids of every story in the feed. Stories are fetched from these a page
+    /// at a time as the list is scrolled through
+    pub ids: Vec<u32>,
+
+    pub list: StatefulList<Story>,
+
+    /// Whether the next page is being fetched
+    pub loading_more: bool,
+
+    /// The fetch before the current one, used to show how stories moved
+    /// after a refresh
+    pub previous: Option<PreviousFetch>,
+
+    pub preview: Preview,
+
+    pub updated: Instant
+}
+
+/// Top comments of the selected story, shown next to the list in the split
+/// layout. They're fetched once the selection has settled on the story
+#[derive(Default)]
+pub struct Preview {
+    /// Story that the selection is on and since when
+    selected: Option<(i32, Instant)>,
+
+    /// Top comments of the stories previewed so far
+    pub comments: HashMap<i32, Vec<Comment>>,
+
+    /// Stories whose comments are being fetched
+    requested: HashSet<i32>
+}
+
+impl Preview {
+    /// Whether the comments of the selected story should be fetched now,
+    /// marking them as requested when they should
+    fn due(&mut self, story: &Story) -> bool {
+        match self.selected {
+            Some((id, since)) if id == story.id => {
+                if since.elapsed() < PREVIEW_DELAY
+                    || self.comments.contains_key(&story.id)
+                    || self.requested.contains(&story.id) {
+                    return false;
+                }
+            }
+            _ => {
+                self.selected = Some((story.id, Instant::now()));
+                return false;
+            }
+        }
+
+        self.requested.insert(story.id)
+    }
+
+    fn loaded(&mut self, story: i32, comments: Vec<Comment>) -> bool {
+        if !self.requested.remove(&story) {
+            return false;
+        }
+        self.comments.insert(story, comments);
+
+        true
+    }
+}
+
+/// Ranks of every story in a feed as of a fetch, and the scores of those that
+/// had been loaded by then
+pub struct PreviousFetch {
+    ranks: HashMap<i32, usize>,
+    scores: HashMap<i32, i32>
+}
+
+/// How a story's rank changed between two fetches of a feed
+#[derive(Debug, PartialEq)]
+pub enum RankChange {
+    New,
+    Up(usize),
+    Down(usize),
+    Same
+}
+
+impl StoriesView {
+    pub fn new(title: &str, feed: Option<Feed>, ids: Vec<u32>, stories: Vec<Story>) -> StoriesView {
+        StoriesView {
+            title: title.to_string(),
+            feed,
+            ids,
+            list: StatefulList::with_items(stories),
+            loading_more: false,
+            previous: None,
+            preview: Preview::default(),
+            updated: Instant::now()
+        }
+    }
+
+    /// Whether the feed has stories that haven't been fetched yet
+    pub fn has_more(&self) -> bool {
+        self.list.items.len() < self.ids.len()
+    }
+
+    /// Ids of the next page of stories, if the selection is close enough to
+    /// the bottom of the list that it should be loaded
+    fn next_page(&self) -> Option<Vec<u32>> {
+        let start = self.list.items.len();
+        let selected = self.list.state.selected().unwrap_or_default();
+        if self.loading_more || !self.has_more() || selected + LOAD_AHEAD < start {
+            return None;
+        }
+
+        let end = (start + PAGE_SIZE).min(self.ids.len());
+        Some(self.ids[start..end].to_vec())
+    }
+
+    /// Replaces the stories with a fresh fetch, keeping the same story
+    /// selected when it's still in the feed
+    pub fn update(&mut self, ids: Vec<u32>, stories: Vec<Story>) {
+        // Ranks are taken from the ids rather than the loaded stories, so
+        // that pages loaded after the refresh aren't all taken to be new
+        let ranks = if self.ids.is_empty() {
+            self.list.items.iter().enumerate().map(|(rank, story)| (story.id, rank)).collect()
+        } else {
+            self.ids.iter().enumerate().map(|(rank, id)| (*id as i32, rank)).collect()
+        };
+        self.previous = Some(PreviousFetch {
+            ranks,
+            scores: self.list.items.iter().map(|story| (story.id, story.score)).collect()
+        });
+        self.ids = ids;
+        self.list.replace_items(stories, |story| story.id);
+        self.loading_more = false;
+        self.updated = Instant::now();
+
+        // Previews are fetched again rather than showing comments from
+        // before the refresh
+        self.preview.comments.clear();
+    }
+
+    pub fn rank_change(&self, rank: usize, story: &Story) -> RankChange {
+        let previous = match &self.previous {
+            Some(x) => x,
+            None => return RankChange::Same
+        };
+
+        match previous.ranks.get(&story.id) {
+            None => RankChange::New,
+            Some(previous_rank) if *previous_rank > rank => RankChange::Up(previous_rank - rank),
+            Some(previous_rank) if *previous_rank < rank => RankChange::Down(rank - previous_rank),
+            Some(_) => RankChange::Same
+        }
+    }
+
+    /// Points gained since the previous fetch
+    pub fn score_delta(&self, story: &Story) -> i32 {
+        match self.previous.as_ref().and_then(|previous| previous.scores.get(&story.id)) {
+            Some(score) => story.score - score,
+            None => 0
+        }
+    }
+}
+
+/// A comment thread, rooted at a story or at a single item
+pub struct CommentsView {
+    pub title: String,
+
+    /// Story the thread belongs to, when the thread is rooted at a story
+    pub story: Option<Story>,
+
+    /// Id of the story or comment at the root of the thread
+    pub root: i32,
+
+    /// Options of the story when it's a poll
+    pub poll_options: Vec<PollOption>,
+
+    pub list: StatefulList<Comment>,
+
+    /// Order that the replies to each comment are shown in
+    pub order: SortOrder,
+
+    /// Ids of the comments leading down to a comment that was gone to by id,
+    /// which is selected once the replies on the way to it have been loaded
+    pub target: Vec<i32>,
+
+    pub updated: Instant
+}
+
+impl CommentsView {
+    pub fn new(title: &str, story: Option<Story>, root: i32, comments: Vec<Comment>) -> CommentsView {
+        CommentsView {
+            title: title.to_string(),
+            story,
+            root,
+            poll_options: Vec::new(),
+            list: StatefulList::with_items(comments),
+            order: SortOrder::Ranked,
+            target: Vec::new(),
+            updated: Instant::now()
+        }
+    }
+
+    /// Moves the selection as close to the target comment as what's loaded so
+    /// far allows. Returns the comments on the way to it whose replies were
+    /// deferred, which have to be loaded before it can be reached. The target
+    /// is given up on once the replies it should be among have been loaded
+    /// without it, e.g. when it was deleted
+    pub fn follow_target(&mut self) -> Vec<Comment> {
+        let closest = self
+            .target
+            .iter()
+            .rev()
+            .find_map(|id| self.list.items.iter().position(|comment| comment.id == *id));
+        if let Some(index) = closest {
+            self.list.state.select(Some(index));
+            let comment = &self.list.items[index];
+            if self.target.last() == Some(&comment.id) || comment.replies_loaded {
+                self.target.clear();
+                return Vec::new();
+            }
+        }
+
+        let mut deferred = Vec::new();
+        for comment in self.list.items.iter_mut() {
+            if comment.replies_deferred && self.target.contains(&comment.id) {
+                comment.replies_deferred = false;
+                deferred.push(comment.clone());
+            }
+        }
+
+        deferred
+    }
+
+    /// Comments whose replies have been loaded after being deferred, which a
+    /// refresh loads again so that they stay expanded
+    pub fn expanded(&self) -> HashSet<i32> {
+        self.list
+            .items
+            .iter()
+            .filter(|comment| comment.replies_loaded && !comment.kids.is_empty())
+            .map(|comment| comment.id)
+            .collect()
+    }
+
+    /// Replaces the comments with a fresh fetch, keeping the same comment
+    /// selected when it's still in the thread. Replies that were expanded
+    /// while the fetch was underway are carried over from the old list
+    pub fn update(&mut self, comments: Vec<Comment>) {
+        let mut merged = Vec::with_capacity(comments.len());
+        for mut comment in comments {
+            let expanded = match self.list.items.iter().position(|old| old.id == comment.id && old.replies_loaded) {
+                Some(index) if !comment.replies_loaded => index,
+                _ => {
+                    merged.push(comment);
+                    continue;
+                }
+            };
+
+            comment.replies_loaded = true;
+            comment.replies_deferred = false;
+            let depth = comment.depth;
+            merged.push(comment);
+            merged.extend(
+                self.list.items[expanded + 1..]
+                    .iter()
+                    .take_while(|old| old.depth > depth)
+                    .cloned()
+            );
+        }
+
+        self.list.replace_items(merged, |comment| comment.id);
+        self.updated = Instant::now();
+    }
+
+    /// Author of the story the thread belongs to
+    pub fn op(&self) -> &str {
+        match &self.story {
+            Some(story) => &story.by,
+            None => ""
+        }
+    }
+
+    /// Reorders the thread, keeping the same comment selected
+    pub fn sort(&mut self, order: SortOrder) {
+        self.order = order;
+        let sorted = comments::sort_flat(self.list.items.clone(), order, self.op());
+        self.list.replace_items(sorted, |comment| comment.id);
+    }
+
+    /// Inserts the replies to a comment directly below it, in the order of the
+    /// thread. Replies that were already loaded, e.g. by a refresh, are left alone
+    pub fn insert_replies(&mut self, parent: i32, mut replies: Vec<Comment>) {
+        comments::sort_comments(&mut replies, self.order, self.op());
+
+        let index = match self.list.items.iter().position(|comment| comment.id == parent) {
+            Some(x) => x,
+            None => return
+        };
+        if self.list.items[index].replies_loaded {
+            return;
+        }
+        self.list.items[index].replies_loaded = true;
+
+        // Keeping the selection on the same comment as items are added above it
+        let count = replies.len();
+        if let Some(selected) = self.list.state.selected() {
+            if selected > index {
+                self.list.state.select(Some(selected + count));
+            }
+        }
+
+        let at = index + 1;
+        self.list.items.splice(at..at, replies);
+    }
+}
+
+pub struct HistoryView {
+    pub filter: HistoryFilter,
+    pub list: StatefulList<HistoryEntry>
+}
+
+pub struct UserView {
+    pub user: User,
+    pub scroll: u16
+}
+
+/// Description of a job posting, which has no comments to show
+pub struct JobView {
+    pub story: Story,
+    pub scroll: u16
+}
+
+/// Article read out of the page a story links to
+pub struct ReaderView {
+    pub article: Article,
+    pub scroll: u16,
+
+    /// Lines that fit on the screen, recorded when it's drawn, for paging
+    pub page: u16
+}
+
+/// Preview of a reply or submission before it's posted
+pub struct DraftView {
+    pub draft: Draft,
+    pub scroll: u16
+}
+
+/// Every screen the application can show. Views are kept on a stack so that
+/// going back restores the previous one exactly as it was left
+pub enum View {
+    Stories(StoriesView),
+    Search(StoriesView),
+    Comments(CommentsView),
+    Item(CommentsView),
+    User(UserView),
+    Job(JobView),
+    Reader(ReaderView),
+    Draft(DraftView),
+    Saved(StatefulList<Bookmark>),
+    History(HistoryView)
+}
+
+impl View {
+    fn next(&mut self) {
+        match self {
+            View::Stories(view) | View::Search(view) => view.list.next(),
+            View::Comments(view) | View::Item(view) => view.list.next(),
+            View::User(view) => view.scroll += 1,
+            View::Job(view) => view.scroll += 1,
+            View::Reader(view) => view.scroll = view.scroll.saturating_add(1),
+            View::Draft(view) => view.scroll += 1,
+            View::Saved(list) => list.next(),
+            View::History(view) => view.list.next()
+        }
+    }
+
+    fn previous(&mut self) {
+        match self {
+            View::Stories(view) | View::Search(view) => view.list.previous(),
+            View::Comments(view) | View::Item(view) => view.list.previous(),
+            View::User(view) => view.scroll = view.scroll.saturating_sub(1),
+            View::Job(view) => view.scroll = view.scroll.saturating_sub(1),
+            View::Reader(view) => view.scroll = view.scroll.saturating_sub(1),
+            View::Draft(view) => view.scroll = view.scroll.saturating_sub(1),
+            View::Saved(list) => list.previous(),
+            View::History(view) => view.list.previous()
+        }
+    }
+
+    fn go_to_top(&mut self) {
+        match self {
+            View::Stories(view) | View::Search(view) => view.list.go_to_top(),
+            View::Comments(view) | View::Item(view) => view.list.go_to_top(),
+            View::User(view) => view.scroll = 0,
+            View::Job(view) => view.scroll = 0,
+            View::Reader(view) => view.scroll = 0,
+            View::Draft(view) => view.scroll = 0,
+            View::Saved(list) => list.go_to_top(),
+            View::History(view) => view.list.go_to_top()
+        }
+    }
+
+    fn go_to_bottom(&mut self) {
+        match self {
+            View::Stories(view) | View::Search(view) => view.list.go_to_bottom(),
+            View::Comments(view) | View::Item(view) => view.list.go_to_bottom(),
+            View::User(_) | View::Job(_) | View::Draft(_) => {}
+            // Past the end, which drawing brings back to the last screenful
+            View::Reader(view) => view.scroll = u16::MAX,
+            View::Saved(list) => list.go_to_bottom(),
+            View::History(view) => view.list.go_to_bottom()
+        }
+    }
+}
+
+/// What the text typed into the prompt will be applied to
+pub enum PromptTarget {
+    Tags,
+    Note,
+    HistorySearch,
+    StorySearch,
+    Rank,
+    Item
+}
+
+pub struct App {
+    /// Views that can be returned to, the current view being the last one
+    pub views: Vec<View>,
+
+    /// Views that were navigated back from, most recent last
+    pub forward: Vec<View>,
+
+    pub bookmarks: Vec<Bookmark>,
+    pub history: Vec<HistoryEntry>,
+
+    /// Local copy of the account's favorite and upvoted stories
+    pub favorites: Favorites,
+
+    /// Text input for editing bookmarks and searching
+    pub prompt: Option<(PromptTarget, Prompt)>,
+
+    /// Outcome of the last action taken with the account, shown until the
+    /// next key press
+    pub status: Option<String>,
+
+    /// Logged in account, set up the first time an action needs it
+    session: Option<Session>,
+
+    /// Order that comment threads are shown in, kept when opening another one
+    comment_order: SortOrder,
+
+    /// Whether the indentation of comment threads is capped at the configured
+    /// `max_indent`
+    pub compact_threads: bool,
+
+    /// Whether stories are shown next to a preview of the selected story
+    pub split_view: bool,
+
+    /// Columns of the terminal as of the last draw
+    pub width: u16,
+
+    /// Articles read so far, by url, so going back to one doesn't fetch it again
+    articles: HashMap<String, Article>,
+
+    /// Draft waiting to be handed to the editor, which the event loop does
+    /// since it owns the terminal
+    pub editing: Option<Draft>,
+
+    pub config: Config,
+
+    /// Used by background tasks to hand their results back to the event loop
+    sender: mpsc::Sender<Event<Key>>,
+
+    /// When the last background refresh was started
+    refresh_started: Option<Instant>,
+
+    /// When the updates endpoint was last polled
+    updates_started: Option<Instant>,
+
+    /// Newest item id seen on the site, used to tell when new items arrived
+    max_item: i32,
+
+    /// Open streaming subscriptions by the API path they watch
+    subscriptions: HashMap<String, JoinHandle<()>>,
+
+    /// Subscription path and when the first of its changes that the view
+    /// hasn't been refreshed for arrived
+    streamed: Option<(String, Instant)>,
+
+    pub should_quit: bool
+}
+
+impl App {
+    pub fn new(top_story_ids: Vec<u32>, top_stories: Vec<Story>, config: Config, sender: mpsc::Sender<Event<Key>>) -> Result<App, Box<dyn Error>> {
+        let stories = StoriesView::new(Feed::Top.title(), Some(Feed::Top), top_story_ids, top_stories);
+
+        Ok(App {
+            views: vec![View::Stories(stories)],
+            forward: Vec::new(),
+            bookmarks: bookmarks::load()?,
+            history: history::load()?,
+            favorites: favorites::load()?,
+            prompt: None,
+            status: None,
+            session: None,
+            comment_order: SortOrder::Ranked,
+            compact_threads: config.compact_threads,
+            split_view: config.split_view,
+            width: 0,
+            articles: HashMap::new(),
+            editing: None,
+            config,
+            sender,
+            refresh_started: None,
+            updates_started: None,
+            max_item: 0,
+            subscriptions: HashMap::new(),
+            streamed: None,
+            should_quit: false
+        })
+    }
+
+    /// Starts background work that is due: polling for live updates, and
+    /// refreshing the current feed or comment thread once it's older than the
+    /// configured interval
+    pub fn on_tick(&mut self) {
+        self.poll_updates();
+        self.load_preview();
+        self.refresh_streamed();
+
+        if self.config.refresh_interval == 0 {
+            return;
+        }
+
+        let interval = Duration::from_secs(self.config.refresh_interval);
+        if let Some(started) = self.refresh_started {
+            if started.elapsed() < interval {
+                return;
+            }
+        }
+
+        let stale = match self.current() {
+            View::Stories(view) => view.updated.elapsed() >= interval,
+            View::Comments(view) | View::Item(view) => view.updated.elapsed() >= interval,
+            _ => false
+        };
+        if stale {
+            self.start_refresh();
+        }
+    }
+
+    /// Refreshes the view once changes pushed over its subscription have been
+    /// gathered for a while, and not sooner than that after the last refresh
+    fn refresh_streamed(&mut self) {
+        let path = match &self.streamed {
+            Some((path, arrived)) if arrived.elapsed() >= STREAM_DEBOUNCE => path.clone(),
+            _ => return
+        };
+        if self.refresh_started.is_some_and(|started| started.elapsed() < STREAM_DEBOUNCE) {
+            return;
+        }
+
+        self.streamed = None;
+        // Dropping the changes of a view that has since been left
+        if self.subscription_path() == Some(path) {
+            self.start_refresh();
+        }
+    }
+
+    /// Whether the split layout is on and the terminal is wide enough for it,
+    /// the list of stories being left on its own otherwise
+    pub fn shows_preview(&self) -> bool {
+        self.split_view && self.width >= MIN_SPLIT_WIDTH
+    }
+
+    /// Fetches the top comments of the selected story for the split layout,
+    /// once the selection has stayed on it for a moment
+    fn load_preview(&mut self) {
+        if !self.shows_preview() {
+            return;
+        }
+
+        let sender = self.sender.clone();
+        let story = match self.current() {
+            View::Stories(view) | View::Search(view) => match view.list.selected().cloned() {
+                Some(story) if view.preview.due(&story) => story,
+                _ => return
+            },
+            _ => return
+        };
+
+        tokio::spawn(async move {
+            let kids: Vec<i32> = story.kids.iter().take(PREVIEW_COMMENTS).copied().collect();
+            let comments = hackernews::comments::get_comments_shallow(&kids, 0).await;
+            let _ = sender.send(Event::Message(Message::PreviewLoaded { story: story.id, comments }));
+        });
+    }
+
+    fn poll_updates(&mut self) {
+        if self.config.live_updates == 0 {
+            return;
+        }
+
+        let interval = Duration::from_secs(self.config.live_updates);
+        if let Some(started) = self.updates_started {
+            if started.elapsed() < interval {
+                return;
+            }
+        }
+        self.updates_started = Some(Instant::now());
+
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let message = match hackernews::updates::poll().await {
+                Ok((updates, max_item)) => Message::ItemsChanged {
+                    items: updates.items,
+                    max_item
+                },
+                Err(error) => Message::RefreshFailed(error.to_string())
+            };
+            let _ = sender.send(Event::Message(message));
+        });
+    }
+
+    /// Refetches the current feed or comment thread in the background. The
+    /// results are merged into the view once they arrive
+    fn start_refresh(&mut self) {
+        let sender = self.sender.clone();
+        match self.current() {
+            View::Stories(view) => {
+                // The account's lists are only synced when asked to
+                let feed = match view.feed {
+                    Some(x) if x.path().is_some() => x,
+                    _ => return
+                };
+
+                // Refetching as many stories as have been paged through
+                let count = view.list.items.len().max(PAGE_SIZE);
+                tokio::spawn(async move {
+                    let message = match hackernews::stories::feed_stories(feed, count).await {
+                        Ok((ids, stories)) => Message::StoriesRefreshed { feed, ids, stories },
+                        Err(error) => Message::RefreshFailed(error.to_string())
+                    };
+                    let _ = sender.send(Event::Message(message));
+                });
+            }
+            View::Comments(view) | View::Item(view) => {
+                let root = view.root;
+                let is_story = view.story.is_some();
+                let order = view.order;
+                let op = view.op().to_string();
+                let expanded = view.expanded();
+                let max_depth = self.config.max_depth();
+                tokio::spawn(async move {
+                    // Stories are refetched as well so new top level comments show up
+                    let parents = if is_story {
+                        match hackernews::stories::get_story(root as u32).await {
+                            Ok(story) => story.kids,
+                            Err(error) => {
+                                let _ = sender.send(Event::Message(Message::RefreshFailed(error.to_string())));
+                                return;
+                            }
+                        }
+                    } else {
+                        vec![root]
+                    };
+
+                    let message = match hackernews::comments::get_comments(&parents, 0, max_depth, &expanded).await {
+                        Ok(mut comments) => {
+                            hackernews::comments::sort_comments(&mut comments, order, &op);
+                            Message::CommentsRefreshed {
+                                root,
+                                comments: hackernews::comments::flatten_comments(&comments)
+                            }
+                        }
+                        Err(error) => Message::RefreshFailed(error.to_string())
+                    };
+                    let _ = sender.send(Event::Message(message));
+                });
+            }
+            _ => return
+        }
+
+        self.refresh_started = Some(Instant::now());
+    }
+
+    /// Fetches the next page of the current feed in the background when the
+    /// selection is getting close to the last loaded story
+    fn load_next_page(&mut self) {
+        let (feed, start, ids) = match self.current() {
+            View::Stories(view) => match (view.feed, view.next_page()) {
+                (Some(feed), Some(ids)) => {
+                    view.loading_more = true;
+                    (feed, view.list.items.len(), ids)
+                }
+                _ => return
+            },
+            _ => return
+        };
+
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let stories = hackernews::stories::get_stories(&ids).await.map_err(|error| error.to_string());
+            let _ = sender.send(Event::Message(Message::StoriesPage { feed, start, stories }));
+        });
+    }
+
+    /// Selects the story at a rank of the current feed, first fetching the
+    /// pages up to it when they haven't been loaded yet
+    async fn go_to_rank(&mut self, rank: usize) -> Result<(), Box<dyn Error>> {
+        let view = match self.current() {
+            View::Stories(view) | View::Search(view) => view,
+            _ => return Ok(())
+        };
+        if rank == 0 || rank > view.ids.len().max(view.list.items.len()) {
+            return Ok(());
+        }
+
+        let loaded = view.list.items.len();
+        if rank > loaded && !view.loading_more {
+            // Rounding up to a whole page
+            let end = (rank.div_ceil(PAGE_SIZE) * PAGE_SIZE).min(view.ids.len());
+            let stories = hackernews::stories::get_stories(&view.ids[loaded..end]).await?;
+            view.list.items.extend(stories);
+        }
+
+        if rank <= view.list.items.len() {
+            view.list.state.select(Some(rank - 1));
+        }
+
+        Ok(())
+    }
+
+    /// API path that the current view is kept up to date with when streaming
+    fn subscription_path(&mut self) -> Option<String> {
+        match self.current() {
+            View::Stories(view) => view.feed.and_then(|feed| feed.path()).map(String::from),
+            View::Comments(view) | View::Item(view) => Some(format!("item/{}.json", view.root)),
+            _ => None
+        }
+    }
+
+    /// Subscribes to changes of what's currently shown, and closes the
+    /// subscriptions of views that are no longer shown
+    pub fn sync_subscriptions(&mut self) {
+        if !self.config.streaming {
+            return;
+        }
+
+        let wanted = self.subscription_path();
+        self.subscriptions.retain(|path, handle| {
+            let keep = Some(path) == wanted.as_ref();
+            if !keep {
+                handle.abort();
+            }
+            keep
+        });
+
+        let path = match wanted {
+            Some(x) => x,
+            None => return
+        };
+        if self.subscriptions.contains_key(&path) {
+            return;
+        }
+
+        let sender = self.sender.clone();
+        let task_path = path.clone();
+        let handle = tokio::spawn(async move {
+            // The first put holds the data as it is now, which is already shown
+            let mut initial = true;
+            let result = hackernews::stream::subscribe(&task_path, |event| {
+                if initial && event.event == "put" {
+                    initial = false;
+                    return;
+                }
+                let message = Message::Streamed {
+                    path: task_path.clone(),
+                    event
+                };
+                let _ = sender.send(Event::Message(message));
+            }).await;
+
+            if let Err(error) = result {
+                let _ = sender.send(Event::Message(Message::RefreshFailed(error.to_string())));
+            }
+            let _ = sender.send(Event::Message(Message::StreamClosed(task_path)));
+        });
+        self.subscriptions.insert(path, handle);
+    }
+
+    /// Merges the results of background work into the views they belong to
+    pub fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::StoriesRefreshed { feed, ids, stories } => {
+                for view in self.views.iter_mut() {
+                    match view {
+                        View::Stories(view) if view.feed == Some(feed) => {
+                            view.update(ids, stories);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Message::StoriesPage { feed, start, stories } => {
+                for view in self.views.iter_mut() {
+                    match view {
+                        View::Stories(view) if view.feed == Some(feed) => {
+                            view.loading_more = false;
+                            match stories {
+                                // Dropping the page if the feed changed in the meantime
+                                Ok(stories) if view.list.items.len() == start => view.list.items.extend(stories),
+                                Ok(_) => {}
+                                Err(error) => {
+                                    log::error!("loading stories failed: {}", error);
+                                    self.status = Some(format!("Failed: {}", error));
+                                }
+                            }
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Message::CommentsRefreshed { root, comments } => {
+                for view in self.views.iter_mut() {
+                    match view {
+                        View::Comments(view) | View::Item(view) if view.root == root => {
+                            view.update(comments.clone());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Message::RepliesLoaded { root, parent, replies } => {
+                let mut deferred = Vec::new();
+                for view in self.views.iter_mut().chain(self.forward.iter_mut()) {
+                    match view {
+                        View::Comments(view) | View::Item(view) if view.root == root => {
+                            view.insert_replies(parent, replies.clone());
+                            deferred.extend(view.follow_target());
+                        }
+                        _ => {}
+                    }
+                }
+                self.load_deferred(root, &deferred);
+            }
+            Message::ItemsChanged { items, max_item } => {
+                let new_items = self.max_item != 0 && max_item > self.max_item;
+                self.max_item = max_item;
+
+                // Only refreshing when something on screen changed. The
+                // changed items were dropped from the cache, so the refresh
+                // refetches those and serves everything else from the cache
+                let affected = match self.current() {
+                    View::Stories(view) => {
+                        new_items || view.list.items.iter().any(|story| items.contains(&story.id))
+                    }
+                    View::Comments(view) | View::Item(view) => {
+                        items.contains(&view.root) || view.list.items.iter().any(|comment| items.contains(&comment.id))
+                    }
+                    _ => false
+                };
+                if affected {
+                    self.start_refresh();
+                }
+            }
+            Message::Streamed { path, event } => {
+                log::info!("{} {} {}", event.event, path, event.path);
+
+                if self.subscription_path().as_ref() == Some(&path) {
+                    if let View::Comments(view) | View::Item(view) = self.current() {
+                        let root = view.root;
+                        hackernews::client::client().invalidate(&[root]);
+                    }
+                    // Left to `refresh_streamed`, along with the changes that
+                    // follow it
+                    if self.streamed.as_ref().map(|(streamed, _)| streamed) != Some(&path) {
+                        self.streamed = Some((path, Instant::now()));
+                    }
+                }
+            }
+            Message::StreamClosed(path) => {
+                // Forgetting the subscription so that it's reopened on the next
+                // sync if it's still wanted
+                self.subscriptions.remove(&path);
+            }
+            Message::PreviewLoaded { story, comments } => {
+                for view in self.views.iter_mut().rev() {
+                    if let View::Stories(view) | View::Search(view) = view {
+                        if view.preview.loaded(story, comments.clone()) {
+                            break;
+                        }
+                    }
+                }
+            }
+            Message::RefreshFailed(error) => {
+                log::error!("refresh failed: {}", error);
+            }
+        }
+    }
+
+    pub fn current(&mut self) -> &mut View {
+        self.views.last_mut().expect("the view stack is never empty")
+    }
+
+    /// Opens a new view on top of the current one. Like a browser, anything
+    /// that could be navigated forward to is dropped
+    pub fn push(&mut self, view: View) {
+        self.forward.clear();
+        self.views.push(view);
+    }
+
+    /// Returns to the previous view, quitting when there is nothing to return to
+    pub fn back(&mut self) {
+        if self.views.len() == 1 {
+            self.should_quit = true;
+            return;
+        }
+
+        if let Some(view) = self.views.pop() {
+            self.forward.push(view);
+        }
+        self.refresh_saved();
+    }
+
+    pub fn forward(&mut self) {
+        if let Some(view) = self.forward.pop() {
+            self.views.push(view);
+        }
+        self.refresh_saved();
+    }
+
+    /// Bookmarks may have been added or removed while the saved view was in
+    /// the background, so it's brought up to date when it's shown again
+    fn refresh_saved(&mut self) {
+        let bookmarks = self.bookmarks.clone();
+        if let View::Saved(list) = self.current() {
+            let selected = list.state.selected().unwrap_or_default();
+            list.items = bookmarks;
+            if selected >= list.items.len() {
+                list.go_to_bottom();
+            }
+        }
+    }
+
+    pub async fn handle_input(&mut self, key: Key) {
+        self.status = None;
+
+        // Failures such as a fetch that still fails after its retries, or a
+        // browser that can't be opened over ssh, are reported in the status
+        // line rather than ending the program
+        if let Err(error) = self.handle_key(key).await {
+            log::error!("{}", error);
+            self.status = Some(format!("Failed: {}", error));
+        }
+
+        // The input may have changed which view is shown, or moved the
+        // selection close enough to the bottom of a feed to load more of it
+        self.sync_subscriptions();
+        self.load_next_page();
+    }
+
+    async fn handle_key(&mut self, key: Key) -> Result<(), Box<dyn Error>> {
+        // While the prompt is open it receives all of the input
+        if self.prompt.is_some() {
+            return self.handle_prompt_input(key).await;
+        }
+
+        // Moving the selection by hand stops it from being moved again once
+        // the comment that was gone to has loaded
+        if let Key::Char('j' | 'k' | 'g' | 'G' | 'J' | 'K' | 'p' | '[' | ']' | 't') = key {
+            if let View::Comments(view) | View::Item(view) = self.current() {
+                view.target.clear();
+            }
+        }
+
+        // Navigation shared by every view
+        match key {
+            Key::Char('q') => {
+                self.back();
+                return Ok(());
+            }
+            Key::Char('h') | Key::Left => {
+                self.back();
+                return Ok(());
+            }
+            Key::Char('l') | Key::Right => {
+                self.forward();
+                return Ok(());
+            }
+            Key::Char('j') => {
+                self.current().next();
+                return Ok(());
+            }
+            Key::Char('k') => {
+                self.current().previous();
+                return Ok(());
+            }
+            Key::Char('g') => {
+                self.current().go_to_top();
+                return Ok(());
+            }
+            Key::Char('G') => {
+                self.current().go_to_bottom();
+                return Ok(());
+            }
+            Key::Char('i') => {
+                self.prompt = Some((PromptTarget::Item, Prompt::new("Go to item (id or URL)", "")));
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        match self.current() {
+            View::Stories(_) | View::Search(_) => self.handle_stories_input(key).await,
+            View::Comments(_) | View::Item(_) => self.handle_comments_input(key).await,
+            View::Saved(_) => self.handle_saved_input(key).await,
+            View::History(_) => self.handle_history_input(key).await,
+            View::Draft(_) => self.handle_draft_input(key).await,
+            View::Reader(_) => self.handle_reader_input(key).await,
+            View::User(_) | View::Job(_) => Ok(())
+        }
+    }
+
+    async fn handle_prompt_input(&mut self, key: Key) -> Result<(), Box<dyn Error>> {
+        let (target, mut prompt) = match self.prompt.take() {
+            Some(x) => x,
+            None => return Ok(())
+        };
+
+        match key {
+            Key::Char('\n') => {
+                let value = prompt.input.trim().to_string();
+                match target {
+                    PromptTarget::Tags => {
+                        let tags = value
+                            .split(',')
+                            .map(|tag| tag.trim().to_string())
+                            .filter(|tag| !tag.is_empty())
+                            .collect();
+                        self.update_selected_bookmark(|bookmark| bookmark.tags = tags)?;
+                    }
+                    PromptTarget::Note => {
+                        self.update_selected_bookmark(|bookmark| bookmark.note = value)?;
+                    }
+                    PromptTarget::HistorySearch => {
+                        // Leaving the prompt open until the query is valid
+                        let filter = match HistoryFilter::parse(&value) {
+                            Ok(x) => x,
+                            Err(_) => {
+                                self.prompt = Some((target, prompt));
+                                return Ok(());
+                            }
+                        };
+                        let results = history::search(&self.history, &filter);
+                        if let View::History(view) = self.current() {
+                            view.filter = filter;
+                            view.list = StatefulList::with_items(results);
+                        }
+                    }
+                    PromptTarget::StorySearch => {
+                        let query = value.to_lowercase();
+                        let results: Vec<Story> = match self.current() {
+                            View::Stories(view) | View::Search(view) => view
+                                .list
+                                .items
+                                .iter()
+                                .filter(|story| story.title.to_lowercase().contains(&query))
+                                .cloned()
+                                .collect(),
+                            _ => Vec::new()
+                        };
+                        self.push(View::Search(StoriesView::new(&format!("Search: {}", value), None, Vec::new(), results)));
+                    }
+                    PromptTarget::Rank => {
+                        if let Ok(rank) = value.parse::<usize>() {
+                            self.go_to_rank(rank).await?;
+                        }
+                    }
+                    PromptTarget::Item => {
+                        // Leaving the prompt open until the input names an item
+                        let id = match hackernews::items::parse_item_id(&value) {
+                            Some(x) => x,
+                            None => {
+                                self.prompt = Some((target, prompt));
+                                return Ok(());
+                            }
+                        };
+                        self.go_to_item(id).await?;
+                    }
+                }
+            }
+            Key::Esc => {}
+            Key::Backspace => {
+                prompt.pop();
+                self.prompt = Some((target, prompt));
+            }
+            Key::Char(c) => {
+                prompt.push(c);
+                self.prompt = Some((target, prompt));
+            }
+            _ => {
+                self.prompt = Some((target, prompt));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_stories_input(&mut self, key: Key) -> Result<(), Box<dyn Error>> {
+        let story = match self.current() {
+            View::Stories(view) | View::Search(view) => view.list.selected().cloned(),
+            _ => None
+        };
+
+        match key {
+            Key::Char('c') => {
+                if let Some(story) = story {
+                    history::record(&mut self.history, &story)?;
+                    self.open_comments(&story).await?;
+                }
+            }
+            Key::Char('\n') => {
+                if let Some(story) = story {
+                    history::record(&mut self.history, &story)?;
+                    if self.config.open_in_reader {
+                        self.read_story(&story).await;
+                    } else {
+                        self.open_in_browser(&story.url)?;
+                    }
+                }
+            }
+            Key::Char('a') => {
+                if let Some(story) = story {
+                    history::record(&mut self.history, &story)?;
+                    self.read_story(&story).await;
+                }
+            }
+            Key::Char('s') => {
+                if let Some(story) = story {
+                    bookmarks::toggle(&mut self.bookmarks, Bookmark::from_story(&story));
+                    bookmarks::save(&self.bookmarks)?;
+                }
+            }
+            Key::Char('u') => {
+                if let Some(story) = story {
+                    self.open_user(&story.by).await?;
+                }
+            }
+            Key::Char('v') => {
+                if let Some(story) = story {
+                    self.act(Action::Upvote(story.id)).await;
+                }
+            }
+            Key::Char('V') => {
+                if let Some(story) = story {
+                    self.act(Action::Unvote(story.id)).await;
+                }
+            }
+            Key::Char('f') => {
+                if let Some(story) = story {
+                    self.act(Action::Favorite(story.id)).await;
+                }
+            }
+            Key::Char('F') => {
+                if let Some(story) = story {
+                    self.act(Action::Unfavorite(story.id)).await;
+                }
+            }
+            Key::Char('R') => {
+                if let Some(story) = story {
+                    self.editing = Some(Draft::reply(story.id, &story.title));
+                }
+            }
+            Key::Char('N') => {
+                self.editing = Some(Draft::submission());
+            }
+            Key::Char('/') => {
+                self.prompt = Some((PromptTarget::StorySearch, Prompt::new("Search titles", "")));
+            }
+            Key::Char('#') => {
+                self.prompt = Some((PromptTarget::Rank, Prompt::new("Go to rank", "")));
+            }
+            Key::Char('P') => {
+                self.split_view = !self.split_view;
+            }
+            Key::Char('J') => {
+                let (ids, stories) = hackernews::stories::feed_stories(Feed::Jobs, PAGE_SIZE).await?;
+                self.push(View::Stories(StoriesView::new(Feed::Jobs.title(), Some(Feed::Jobs), ids, stories)));
+            }
+            Key::Char('L') => {
+                self.open_account_list(Feed::Favorites).await?;
+            }
+            Key::Char('U') => {
+                self.open_account_list(Feed::Upvoted).await?;
+            }
+            Key::Char('S') => {
+                let bookmarks = self.bookmarks.clone();
+                self.push(View::Saved(StatefulList::with_items(bookmarks)));
+            }
+            Key::Char('H') => {
+                let filter = HistoryFilter::default();
+                let entries = history::search(&self.history, &filter);
+                self.push(View::History(HistoryView {
+                    filter,
+                    list: StatefulList::with_items(entries)
+                }));
+            }
+            Key::Char('r') => {
+                let feed = match self.current() {
+                    View::Stories(view) => view.feed,
+                    _ => None
+                };
+                match feed {
+                    Some(feed) if feed.site_path().is_some() => self.sync_favorites().await,
+                    Some(feed) => {
+                        if let View::Stories(view) = self.current() {
+                            let count = view.list.items.len().max(PAGE_SIZE);
+                            let (ids, stories) = hackernews::stories::feed_stories(feed, count).await?;
+                            view.update(ids, stories);
+                        }
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn handle_comments_input(&mut self, key: Key) -> Result<(), Box<dyn Error>> {
+        let view = match self.current() {
+            View::Comments(view) | View::Item(view) => view,
+            _ => return Ok(())
+        };
+        let comment = view.list.selected().cloned();
+
+        match key {
+            Key::Char('\n') => {
+                // Loading the replies of a comment marked [load N more replies]
+                let selected = view.list.state.selected().unwrap_or_default();
+                if let Some(comment) = view.list.items.get_mut(selected) {
+                    if comment.replies_deferred {
+                        comment.replies_deferred = false;
+                        let comment = comment.clone();
+                        let root = view.root;
+                        self.load_deferred(root, std::slice::from_ref(&comment));
+                    }
+                }
+            }
+            Key::Char('J') => {
+                view.list.next_parent();
+            }
+            Key::Char('K') => {
+                view.list.previous_parent();
+            }
+            Key::Char('p') => {
+                view.list.go_to_parent();
+            }
+            Key::Char(']') => {
+                view.list.next_sibling();
+            }
+            Key::Char('[') => {
+                view.list.previous_sibling();
+            }
+            Key::Char('t') => {
+                view.list.go_to_root();
+            }
+            Key::Char('y') => {
+                view.list.copy_text_to_clipboard();
+            }
+            Key::Char('o') => {
+                let order = view.order.next();
+                view.sort(order);
+                self.comment_order = order;
+            }
+            Key::Char('n') => {
+                // The view is borrowed from the app, so it's looked up again
+                // once the friends are copied out of the config
+                let friends = self.config.friends.clone();
+                let found = match self.current() {
+                    View::Comments(view) | View::Item(view) => view.list.next_by(&friends),
+                    _ => true
+                };
+                if !found {
+                    self.status = Some(String::from("No comments by friends in this thread"));
+                }
+            }
+            Key::Char('z') => {
+                self.compact_threads = !self.compact_threads;
+            }
+            Key::Char('s') => {
+                if let Some(comment) = comment {
+                    bookmarks::toggle(&mut self.bookmarks, Bookmark::from_comment(&comment));
+                    bookmarks::save(&self.bookmarks)?;
+                }
+            }
+            Key::Char('u') => {
+                if let Some(comment) = comment {
+                    self.open_user(&comment.by).await?;
+                }
+            }
+            Key::Char('v') => {
+                if let Some(comment) = comment {
+                    self.act(Action::Upvote(comment.id)).await;
+                }
+            }
+            Key::Char('V') => {
+                if let Some(comment) = comment {
+                    self.act(Action::Unvote(comment.id)).await;
+                }
+            }
+            Key::Char('f') => {
+                if let Some(comment) = comment {
+                    self.act(Action::Favorite(comment.id)).await;
+                }
+            }
+            Key::Char('F') => {
+                if let Some(comment) = comment {
+                    self.act(Action::Unfavorite(comment.id)).await;
+                }
+            }
+            Key::Char('R') => {
+                if let Some(comment) = comment {
+                    self.editing = Some(Draft::reply(comment.id, &comment.by));
+                }
+            }
+            Key::Char('C') => {
+                if let Some(story) = &view.story {
+                    self.editing = Some(Draft::reply(story.id, &story.title));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn handle_reader_input(&mut self, key: Key) -> Result<(), Box<dyn Error>> {
+        let view = match self.current() {
+            View::Reader(view) => view,
+            _ => return Ok(())
+        };
+
+        match key {
+            Key::Char(' ') | Key::PageDown => {
+                view.scroll = view.scroll.saturating_add(view.page.max(1));
+            }
+            Key::Char('b') | Key::PageUp => {
+                view.scroll = view.scroll.saturating_sub(view.page.max(1));
+            }
+            Key::Char('o') => {
+                let url = view.article.url.clone();
+                self.open_in_browser(&url)?;
+            }
+            Key::Char('r') => {
+                // Keeping the article that's open when the page can't be fetched
+                let url = view.article.url.clone();
+                match reader::fetch(&url).await {
+                    Ok(article) => {
+                        self.articles.insert(url, article.clone());
+                        if let View::Reader(view) = self.current() {
+                            view.article = article;
+                        }
+                    }
+                    Err(error) => {
+                        log::error!("failed to read {}: {}", url, error);
+                        self.status = Some(format!("Failed: {}", error));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn handle_draft_input(&mut self, key: Key) -> Result<(), Box<dyn Error>> {
+        let draft = match self.current() {
+            View::Draft(view) => view.draft.clone(),
+            _ => return Ok(())
+        };
+
+        match key {
+            Key::Char('\n') => {
+                let posted = self.act(draft.action()).await;
+                if posted {
+                    // Dropping the draft rather than leaving it to go forward to
+                    self.views.pop();
+                    if let Draft::Reply { .. } = draft {
+                        self.start_refresh();
+                    }
+                }
+            }
+            Key::Char('e') => {
+                self.editing = Some(draft);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Takes a draft back from the editor and shows a preview of it, replacing
+    /// the preview it was edited from
+    pub fn finish_editing(&mut self, mut draft: Draft, edited: Result<String, Box<dyn Error>>) {
+        let contents = match edited {
+            Ok(x) => x,
+            Err(error) => {
+                self.status = Some(format!("Failed: {}", error));
+                return;
+            }
+        };
+
+        draft.apply_edit(&contents);
+        if draft.is_empty() {
+            self.status = Some(String::from("Discarded empty draft"));
+            return;
+        }
+
+        match self.current() {
+            View::Draft(view) => {
+                view.draft = draft;
+                view.scroll = 0;
+            }
+            _ => self.push(View::Draft(DraftView { draft, scroll: 0 }))
+        }
+    }
+
+    async fn handle_saved_input(&mut self, key: Key) -> Result<(), Box<dyn Error>> {
+        let bookmark = match self.current() {
+            View::Saved(list) => list.selected().cloned(),
+            _ => None
+        };
+        let bookmark = match bookmark {
+            Some(x) => x,
+            None => return Ok(())
+        };
+
+        match key {
+            Key::Char('\n') => {
+                // Refetching the item by id so it can be read even after it's
+                // no longer on the front page
+                match bookmark.kind {
+                    BookmarkKind::Story => {
+                        let story = hackernews::stories::get_story(bookmark.id as u32).await?;
+                        self.open_comments(&story).await?;
+                    }
+                    BookmarkKind::Comment => {
+                        self.open_item(bookmark.id).await?;
+                    }
+                }
+            }
+            Key::Char('o') => {
+                self.open_in_browser(&bookmark.url)?;
+            }
+            Key::Char('t') => {
+                let tags = bookmark.tags.join(", ");
+                self.prompt = Some((PromptTarget::Tags, Prompt::new("Tags (comma separated)", &tags)));
+            }
+            Key::Char('n') => {
+                self.prompt = Some((PromptTarget::Note, Prompt::new("Note", &bookmark.note)));
+            }
+            Key::Char('d') => {
+                bookmarks::toggle(&mut self.bookmarks, bookmark);
+                bookmarks::save(&self.bookmarks)?;
+                self.refresh_saved();
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn handle_history_input(&mut self, key: Key) -> Result<(), Box<dyn Error>> {
+        let entry = match self.current() {
+            View::History(view) => view.list.selected().cloned(),
+            _ => None
+        };
+
+        match key {
+            Key::Char('/') => {
+                self.prompt = Some((PromptTarget::HistorySearch, Prompt::new("Search (title since:YYYY-MM-DD until:YYYY-MM-DD)", "")));
+            }
+            Key::Char('\n') => {
+                if let Some(entry) = entry {
+                    let story = hackernews::stories::get_story(entry.id as u32).await?;
+                    self.open_comments(&story).await?;
+                }
+            }
+            Key::Char('o') => {
+                if let Some(entry) = entry {
+                    self.open_in_browser(&entry.url)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Applies a change to the bookmark selected in the saved view and writes
+    /// the bookmarks to disk
+    fn update_selected_bookmark<F: FnOnce(&mut Bookmark)>(&mut self, update: F) -> Result<(), Box<dyn Error>> {
+        let id = match self.current() {
+            View::Saved(list) => match list.selected() {
+                Some(bookmark) => bookmark.id,
+                None => return Ok(())
+            },
+            _ => return Ok(())
+        };
+
+        if let Some(bookmark) = self.bookmarks.iter_mut().find(|b| b.id == id) {
+            update(bookmark);
+        }
+        bookmarks::save(&self.bookmarks)?;
+        self.refresh_saved();
+
+        Ok(())
+    }
+
+    /// Opens the comments of a story. Only the top level comments are waited
+    /// on, the replies below them are loaded in the background. Job postings
+    /// can't be commented on, so their description is shown instead
+    async fn open_comments(&mut self, story: &Story) -> Result<(), Box<dyn Error>> {
+        if story.is_job() {
+            self.push(View::Job(JobView {
+                story: story.clone(),
+                scroll: 0
+            }));
+            return Ok(());
+        }
+
+        let mut comments = hackernews::comments::get_comments_shallow(&story.kids, 0).await;
+        hackernews::comments::defer_replies(&mut comments, self.config.max_depth());
+        hackernews::comments::sort_comments(&mut comments, self.comment_order, &story.by);
+        self.load_replies(story.id, &comments, self.config.max_depth());
+
+        let mut view = CommentsView::new("Comments", Some(story.clone()), story.id, comments);
+        view.order = self.comment_order;
+        if story.is_poll() {
+            view.poll_options = hackernews::polls::get_poll_options(&story.parts).await;
+        }
+        self.push(View::Comments(view));
+
+        Ok(())
+    }
+
+    /// Opens a single comment along with its replies
+    async fn open_item(&mut self, id: i32) -> Result<(), Box<dyn Error>> {
+        let mut comments = hackernews::comments::get_comments_shallow(&[id], 0).await;
+        hackernews::comments::defer_replies(&mut comments, self.config.max_depth());
+        self.load_replies(id, &comments, self.config.max_depth());
+
+        let mut view = CommentsView::new(&format!("Item {}", id), None, id, comments);
+        view.order = self.comment_order;
+        self.push(View::Item(view));
+
+        Ok(())
+    }
+
+    /// Opens any item by id. Stories, polls and jobs are opened as they are
+    /// from a feed, while comments are shown in the thread of their story with
+    /// the selection moved to them
+    pub async fn go_to_item(&mut self, id: i32) -> Result<(), Box<dyn Error>> {
+        let (story, path) = hackernews::items::find_root(id).await?;
+        self.open_comments(&story).await?;
+
+        let (root, deferred) = match self.current() {
+            View::Comments(view) if !path.is_empty() => {
+                view.target = path;
+                (view.root, view.follow_target())
+            }
+            _ => return Ok(())
+        };
+        self.load_deferred(root, &deferred);
+
+        Ok(())
+    }
+
+    /// Fetches replies that were left out by the lazy depth, along with as
+    /// many levels below them
+    fn load_deferred(&self, root: i32, comments: &[Comment]) {
+        for comment in comments {
+            self.load_replies(root, std::slice::from_ref(comment), Some(comment.depth + 1 + self.config.lazy_depth));
+        }
+    }
+
+    /// Loads the replies below the given comments in the background, handing
+    /// each batch to the thread rooted at `root` as it arrives
+    fn load_replies(&self, root: i32, comments: &[Comment], max_depth: Option<i32>) {
+        let sender = self.sender.clone();
+        let comments = comments.to_vec();
+        tokio::spawn(async move {
+            hackernews::comments::load_replies(&comments, max_depth, |parent, replies| {
+                let message = Message::RepliesLoaded { root, parent, replies };
+                let _ = sender.send(Event::Message(message));
+            }).await;
+        });
+    }
+
+    /// Takes an action with the account and reports how it went in the status
+    /// line. Failures, such as not having credentials set up, leave the
+    /// program running. Returns whether the action succeeded
+    async fn act(&mut self, action: Action) -> bool {
+        let done = match action {
+            Action::Upvote(_) => "Upvoted",
+            Action::Unvote(_) => "Unvoted",
+            Action::Favorite(_) => "Favorited",
+            Action::Unfavorite(_) => "Unfavorited",
+            Action::Reply { .. } => "Replied",
+            Action::Submit { .. } => "Submitted"
+        };
+
+        match self.perform(&action).await {
+            Ok(()) => {
+                self.status = Some(done.to_string());
+                true
+            }
+            Err(error) => {
+                log::error!("{} failed: {}", done.to_lowercase(), error);
+                self.status = Some(format!("Failed: {}", error));
+                false
+            }
+        }
+    }
+
+    /// Logs in again once when the saved session turns out to have expired
+    async fn perform(&mut self, action: &Action) -> Result<(), Box<dyn Error>> {
+        let result = self.session().await?.perform(action).await;
+        match result {
+            Err(error) if error.is::<LoggedOut>() => {
+                let session = self.log_in().await?;
+                let result = session.perform(action).await;
+                self.session = Some(session);
+                result
+            }
+            result => result
+        }
+    }
+
+    /// Opens one of the account's lists from the local copy, which is synced
+    /// with the website first when it never has been
+    async fn open_account_list(&mut self, feed: Feed) -> Result<(), Box<dyn Error>> {
+        if self.favorites.synced == 0 {
+            self.sync_favorites().await;
+        }
+
+        let ids = self.favorites.ids(feed);
+        let stories = hackernews::stories::get_stories(&ids[..PAGE_SIZE.min(ids.len())]).await?;
+        self.push(View::Stories(StoriesView::new(feed.title(), Some(feed), ids, stories)));
+
+        Ok(())
+    }
+
+    /// Fetches the account's lists from the website into the local copy, and
+    /// into the views showing them
+    async fn sync_favorites(&mut self) {
+        let result = match self.session().await {
+            Ok(session) => fetch_favorites(session).await,
+            Err(error) => Err(error)
+        };
+        let result = match result {
+            Err(error) if error.is::<LoggedOut>() => match self.log_in().await {
+                Ok(session) => {
+                    let result = fetch_favorites(&session).await;
+                    self.session = Some(session);
+                    result
+                }
+                Err(error) => Err(error)
+            },
+            result => result
+        };
+
+        let synced = match result {
+            Ok(x) => x,
+            Err(error) => {
+                log::error!("sync failed: {}", error);
+                self.status = Some(format!("Failed: {}", error));
+                return;
+            }
+        };
+        if let Err(error) = favorites::save(&synced) {
+            log::error!("saving favorites failed: {}", error);
+        }
+        self.status = Some(format!("Synced {} favorites and {} upvoted stories", synced.favorites.len(), synced.upvoted.len()));
+
+        for view in self.views.iter_mut().chain(self.forward.iter_mut()) {
+            if let View::Stories(view) = view {
+                if let Some(feed) = view.feed.filter(|feed| feed.site_path().is_some()) {
+                    let ids = synced.ids(feed);
+                    let count = view.list.items.len().max(PAGE_SIZE).min(ids.len());
+                    match hackernews::stories::get_stories(&ids[..count]).await {
+                        Ok(stories) => view.update(ids, stories),
+                        Err(error) => {
+                            log::error!("loading stories failed: {}", error);
+                            self.status = Some(format!("Failed: {}", error));
+                        }
+                    }
+                }
+            }
+        }
+        self.favorites = synced;
+    }
+
+    /// The logged in account, picking up the cookie of an earlier login when
+    /// there is one
+    async fn session(&mut self) -> Result<&Session, Box<dyn Error>> {
+        if self.session.is_none() {
+            let session = match storage::session::load()? {
+                Some(saved) => Session::new(&self.config.site_url, &saved.username, &saved.cookie),
+                None => self.log_in().await?
+            };
+            self.session = Some(session);
+        }
+
+        match &self.session {
+            Some(session) => Ok(session),
+            None => Err(Box::new(LoggedOut))
+        }
+    }
+
+    /// Logs in with the configured credentials and saves the session cookie
+    async fn log_in(&self) -> Result<Session, Box<dyn Error>> {
+        let credentials = match self.config.credentials()? {
+            Some(x) => x,
+            None => return Err("no username set in config.json or secrets.json".into())
+        };
+
+        let session = Session::login(&self.config.site_url, &credentials.username, &credentials.password).await?;
+        storage::session::save(&SavedSession {
+            username: session.username.clone(),
+            cookie: session.cookie.clone()
+        })?;
+
+        Ok(session)
+    }
+
+    /// Opens a link in the default browser. Text posts such as Ask HN have no
+    /// link, which is reported rather than handed to the browser
+    fn open_in_browser(&mut self, url: &str) -> Result<(), Box<dyn Error>> {
+        if url.is_empty() {
+            self.status = Some(String::from("Nothing to open, there's no link"));
+            return Ok(());
+        }
+
+        webbrowser::open(url)?;
+        Ok(())
+    }
+
+    /// Opens the page a story links to in the reader. Text posts have no page,
+    /// their text is shown with the comments instead
+    async fn read_story(&mut self, story: &Story) {
+        if story.url.is_empty() {
+            self.status = Some(String::from("Nothing to read, the story has no link"));
+            return;
+        }
+
+        self.open_article(&story.url).await;
+    }
+
+    /// Opens a page in the reader, fetching it unless it was read before.
+    /// Failures are reported in the status line
+    pub async fn open_article(&mut self, url: &str) {
+        let article = match self.articles.get(url).cloned() {
+            Some(x) => x,
+            None => match reader::fetch(url).await {
+                Ok(article) => {
+                    self.articles.insert(url.to_string(), article.clone());
+                    article
+                }
+                Err(error) => {
+                    log::error!("failed to read {}: {}", url, error);
+                    self.status = Some(format!("Failed: {}", error));
+                    return;
+                }
+            }
+        };
+
+        self.show_article(article);
+    }
+
+    /// Opens an article that was already read in the reader
+    pub fn show_article(&mut self, article: Article) {
+        self.push(View::Reader(ReaderView { article, scroll: 0, page: 0 }));
+    }
+
+    async fn open_user(&mut self, user_id: &str) -> Result<(), Box<dyn Error>> {
+        if user_id.is_empty() {
+            return Ok(());
+        }
+
+        let user = hackernews::users::get_user(user_id).await?;
+        self.push(View::User(UserView { user, scroll: 0 }));
+
+        Ok(())
+    }
+}
+
+/// Scrapes the account's lists from the website
+async fn fetch_favorites(session: &Session) -> Result<Favorites, Box<dyn Error>> {
+    Ok(Favorites {
+        favorites: session.story_list(Feed::Favorites).await?,
+        upvoted: session.story_list(Feed::Upvoted).await?,
+        synced: chrono::Local::now().timestamp()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: i32, depth: i32, kids: &[i32], replies_loaded: bool) -> Comment {
+        let mut comment: Comment = serde_json::from_value(serde_json::json!({ "id": id, "kids": kids })).unwrap();
+        comment.depth = depth;
+        comment.replies_loaded = replies_loaded;
+        comment.replies_deferred = !replies_loaded && !kids.is_empty();
+        comment
+    }
+
+    fn stories(ids: &[u32]) -> Vec<Story> {
+        ids.iter()
+            .map(|id| serde_json::from_value(serde_json::json!({ "id": id, "score": id * 10 })).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn stories_loaded_after_a_refresh_keep_their_rank() {
+        let ids: Vec<u32> = (1..=60).collect();
+        let mut view = StoriesView::new("", Some(Feed::Top), ids.clone(), stories(&ids[..25]));
+
+        // 30 moved to the top and 61 is new, pushing the stories above 30
+        // down two places and those below it down one
+        let mut refreshed = vec![30, 61];
+        refreshed.extend(ids.iter().filter(|id| **id != 30 && **id != 60));
+        view.update(refreshed.clone(), stories(&refreshed[..25]));
+        view.list.items.extend(stories(&refreshed[25..50]));
+
+        let rank = |id: u32| refreshed.iter().position(|x| *x == id).unwrap();
+        let change = |id: u32| view.rank_change(rank(id), &view.list.items[rank(id)]);
+        assert_eq!(change(30), RankChange::Up(29));
+        assert_eq!(change(61), RankChange::New);
+        assert_eq!(change(1), RankChange::Down(2));
+        // Loaded after the refresh, past the stories loaded before it
+        assert_eq!(change(29), RankChange::Down(2));
+        assert_eq!(change(40), RankChange::Down(1));
+    }
+
+    #[test]
+    fn score_changes_only_count_stories_loaded_before() {
+        let ids: Vec<u32> = (1..=30).collect();
+        let mut view = StoriesView::new("", Some(Feed::Top), ids.clone(), stories(&ids[..2]));
+
+        let mut refreshed = stories(&ids[..3]);
+        for story in refreshed.iter_mut() {
+            story.score += 5;
+        }
+        view.update(ids, refreshed);
+
+        assert_eq!(view.score_delta(&view.list.items[0]), 5);
+        assert_eq!(view.score_delta(&view.list.items[2]), 0);
+    }
+
+    fn ids(view: &CommentsView) -> Vec<i32> {
+        view.list.items.iter().map(|comment| comment.id).collect()
+    }
+
+    #[test]
+    fn update_keeps_expanded_replies() {
+        let mut view = CommentsView::new("", None, 0, vec![
+            comment(1, 0, &[2], true),
+            comment(2, 1, &[3], false),
+            comment(4, 0, &[], true)
+        ]);
+        view.insert_replies(2, vec![comment(3, 2, &[], true)]);
+        view.list.state.select(Some(2));
+        assert_eq!(view.expanded(), vec![1, 2].into_iter().collect());
+
+        // A refresh that started before the replies were expanded, and that
+        // brings a new top level comment
+        view.update(vec![
+            comment(5, 0, &[], true),
+            comment(1, 0, &[2], true),
+            comment(2, 1, &[3], false),
+            comment(4, 0, &[], true)
+        ]);
+
+        assert_eq!(ids(&view), [5, 1, 2, 3, 4]);
+        assert!(view.list.items[2].replies_loaded);
+        assert_eq!(view.list.selected().map(|comment| comment.id), Some(3));
+    }
+
+    #[test]
+    fn update_leaves_deferred_replies_alone() {
+        let mut view = CommentsView::new("", None, 0, vec![
+            comment(1, 0, &[2], true),
+            comment(2, 1, &[3], false)
+        ]);
+        view.update(vec![
+            comment(1, 0, &[2], true),
+            comment(2, 1, &[3], false)
+        ]);
+
+        assert_eq!(ids(&view), [1, 2]);
+        assert!(view.list.items[1].replies_deferred);
+    }
+
+    #[test]
+    fn follow_target_gives_up_when_the_target_never_arrives() {
+        let mut view = CommentsView::new("", None, 0, vec![
+            comment(1, 0, &[2], false),
+            comment(4, 0, &[], true)
+        ]);
+        // The replies to 1 are already being loaded
+        view.list.items[0].replies_deferred = false;
+        view.target = vec![1, 2];
+        view.list.state.select(Some(1));
+
+        assert!(view.follow_target().is_empty());
+        assert_eq!(view.list.state.selected(), Some(0));
+        assert_eq!(view.target, [1, 2]);
+
+        // Which arrive without 2, e.g. because it was deleted
+        view.insert_replies(1, Vec::new());
+        view.follow_target();
+        assert!(view.target.is_empty());
+
+        view.list.state.select(Some(1));
+        view.follow_target();
+        assert_eq!(view.list.state.selected(), Some(1));
+    }
+
+    #[test]
+    fn follow_target_loads_deferred_replies_on_the_way() {
+        let mut view = CommentsView::new("", None, 0, vec![
+            comment(1, 0, &[2], false)
+        ]);
+        view.list.items[0].replies_deferred = true;
+        view.target = vec![1, 2];
+
+        let deferred = view.follow_target();
+        assert_eq!(deferred.iter().map(|comment| comment.id).collect::<Vec<i32>>(), [1]);
+
+        view.insert_replies(1, vec![comment(2, 1, &[], true)]);
+        view.follow_target();
+        assert_eq!(view.list.state.selected(), Some(1));
+        assert!(view.target.is_empty());
+    }
+}
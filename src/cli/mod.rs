@@ -0,0 +1,119 @@
+use std::error::Error;
+
+use crate::{
+    hackernews::items::parse_item_id,
+    reader::{self, Article, Block},
+    storage::history::{self, HistoryFilter}
+};
+
+/// What to do once the command line has been handled
+pub enum Action {
+    /// The command already ran, so there is nothing left to do
+    Exit,
+    /// Start the terminal interface on the front page
+    Start,
+    /// Start the terminal interface with an item opened
+    OpenItem(i32),
+    /// Start the terminal interface with a page opened in the reader
+    Read(String),
+    /// Start the terminal interface with a saved page opened in the reader
+    ReadFile(Article)
+}
+
+/// Runs a command given on the command line, which either prints its output
+/// and exits or decides how the terminal interface starts
+pub async fn run(args: &[String]) -> Result<Action, Box<dyn Error>> {
+    match args.get(1).map(String::as_str) {
+        Some("history") => {
+            history_command(&args[2..])?;
+            Ok(Action::Exit)
+        }
+        Some("item") => item_command(&args[2..]),
+        Some("read") => read_command(&args[2..]).await,
+        Some(command) => Err(format!("unknown command: {}", command).into()),
+        None => Ok(Action::Start)
+    }
+}
+
+/// `item <id|url>`, where the url is a link to the item on the website
+fn item_command(args: &[String]) -> Result<Action, Box<dyn Error>> {
+    let arg = match args.first() {
+        Some(x) => x,
+        None => return Err("item expects an id or a url".into())
+    };
+
+    match parse_item_id(arg) {
+        Some(id) => Ok(Action::OpenItem(id)),
+        None => Err(format!("not an item id or url: {}", arg).into())
+    }
+}
+
+/// `read [--print] <url|file>`, where `--print` prints the article instead of
+/// opening it in the reader
+async fn read_command(args: &[String]) -> Result<Action, Box<dyn Error>> {
+    let print = args.iter().any(|arg| arg == "--print");
+    let url = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(x) => x,
+        None => return Err("read expects a url or a file".into())
+    };
+    // Saved pages are read here, the terminal interface only fetches urls
+    let article = match reader::local_path(url) {
+        Some(_) => reader::fetch_file(url)?,
+        None if !print => return Ok(Action::Read(url.clone())),
+        None => reader::fetch(url).await?
+    };
+    if !print {
+        return Ok(Action::ReadFile(article));
+    }
+
+    println!("{}\n", article.title);
+    for (index, block) in article.blocks.iter().enumerate() {
+        match block {
+            Block::Heading(level, text) => println!("{} {}", "#".repeat(*level as usize), text),
+            Block::Paragraph(text) | Block::Code(text) => println!("{}", text),
+            Block::Quote(text) => println!("> {}", text.replace('\n', "\n> ")),
+            Block::ListItem(text) => println!("- {}", text)
+        }
+
+        // Items of the same list stay together
+        match (block, article.blocks.get(index + 1)) {
+            (Block::ListItem(_), Some(Block::ListItem(_))) => {}
+            _ => println!()
+        }
+    }
+    for (number, link) in article.links.iter().enumerate() {
+        println!("[{}] {}", number + 1, link);
+    }
+
+    Ok(Action::Exit)
+}
+
+/// `history [--since YYYY-MM-DD] [--until YYYY-MM-DD] [title words...]`
+fn history_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut query: Vec<String> = Vec::new();
+
+    // Translating the flags into the same query syntax the history view uses
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--since" | "--until" => {
+                let date = match args.next() {
+                    Some(x) => x,
+                    None => return Err(format!("{} expects a date", arg).into())
+                };
+                query.push(format!("{}:{}", &arg[2..], date));
+            }
+            _ => query.push(arg.clone())
+        }
+    }
+
+    let filter = HistoryFilter::parse(&query.join(" "))?;
+    for entry in history::search(&history::load()?, &filter) {
+        println!("{}  [{}] {}", entry.opened_date(), entry.score, entry.title);
+        if !entry.url.is_empty() {
+            println!("                  {}", entry.url);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::{error::Error, fs, os::unix::fs::PermissionsExt, path::PathBuf, time::Duration};
+
+use serde::Deserialize;
+
+pub mod theme;
+
+use crate::{
+    config::theme::Theme,
+    hackernews::{
+        client::{Limits, HACKERNEWS_API},
+        session::HACKERNEWS_SITE
+    }
+};
+
+/// User settings read from `$XDG_CONFIG_HOME/hackernews-tui/config.json`.
+/// Every setting is optional and falls back to its default
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Seconds between background refreshes of the open feed or comment
+    /// thread. Auto-refresh is disabled when this is 0
+    pub refresh_interval: u64,
+
+    /// Seconds between polls of the API's updates endpoint. While enabled,
+    /// fetched items are cached and only the ones reported as changed are
+    /// refetched. Live updates are disabled when this is 0
+    pub live_updates: u64,
+
+    /// Subscribe to the open feed and comment thread with the API's
+    /// server-sent events, refreshing them as soon as the server pushes a change
+    pub streaming: bool,
+
+    /// Base url of the Hacker News API
+    pub api_url: String,
+
+    /// Requests to the API allowed to be in flight at once
+    pub max_concurrent_requests: usize,
+
+    /// Sustained rate of requests to the API, 0 for no limit
+    pub requests_per_second: f64,
+
+    /// Seconds before a request to the API times out
+    pub request_timeout: u64,
+
+    /// Times a request is retried after a timeout, a 429 or a 5xx
+    pub max_retries: u32,
+
+    /// Levels of a comment thread fetched when it's opened. Deeper replies
+    /// are only fetched when asked for. Every level is fetched when this is 0
+    pub lazy_depth: i32,
+
+    /// Show the text of dead stories and comments rather than a placeholder
+    #[serde(rename = "showdead")]
+    pub show_dead: bool,
+
+    /// Base url of the website, which is used for actions that need an account
+    pub site_url: String,
+
+    /// Account to log in with for voting and favoriting. The credentials can
+    /// be kept in `secrets.json` instead, next to the config
+    pub username: String,
+    pub password: String,
+
+    /// Users whose comments are highlighted in threads and can be jumped to
+    pub friends: Vec<String>,
+
+    /// Start with comment threads in compact mode, where indentation stops
+    /// growing past `max_indent` levels
+    pub compact_threads: bool,
+
+    /// Levels of indentation shown in compact mode
+    pub max_indent: i32,
+
+    /// Start with stories shown next to a preview of the selected story
+    pub split_view: bool,
+
+    /// Open stories in the reader with enter, rather than in the browser
+    pub open_in_reader: bool,
+
+    pub theme: Theme
+}
+
+/// Login details kept apart from the rest of the config in `secrets.json`
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            refresh_interval: 0,
+            live_updates: 0,
+            streaming: false,
+            api_url: String::from(HACKERNEWS_API),
+            max_concurrent_requests: 8,
+            requests_per_second: 20.0,
+            request_timeout: 10,
+            max_retries: 3,
+            lazy_depth: 0,
+            show_dead: false,
+            site_url: String::from(HACKERNEWS_SITE),
+            username: String::new(),
+            password: String::new(),
+            friends: Vec::new(),
+            compact_threads: false,
+            max_indent: 6,
+            split_view: false,
+            open_in_reader: false,
+            theme: Theme::default()
+        }
+    }
+}
+
+impl Config {
+    /// Depth at which comment replies stop being fetched up front
+    pub fn max_depth(&self) -> Option<i32> {
+        if self.lazy_depth > 0 {
+            Some(self.lazy_depth)
+        } else {
+            None
+        }
+    }
+
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_concurrent: self.max_concurrent_requests,
+            requests_per_second: self.requests_per_second,
+            timeout: Duration::from_secs(self.request_timeout),
+            retries: self.max_retries
+        }
+    }
+
+    /// Credentials from the config, falling back to the secrets file. None
+    /// when neither has a username
+    pub fn credentials(&self) -> Result<Option<Credentials>, Box<dyn Error>> {
+        if !self.username.is_empty() {
+            return Ok(Some(Credentials {
+                username: self.username.clone(),
+                password: self.password.clone()
+            }));
+        }
+
+        let path = config_dir().join("secrets.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        if fs::metadata(&path)?.permissions().mode() & 0o077 != 0 {
+            log::warn!("{} can be read by other users, it should only be readable by you (chmod 600)", path.display());
+        }
+
+        let credentials: Credentials = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if credentials.username.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(credentials))
+    }
+}
+
+fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("hackernews-tui")
+}
+
+pub fn config_path() -> PathBuf {
+    config_dir().join("config.json")
+}
+
+pub fn load() -> Result<Config, Box<dyn Error>> {
+    let path = config_path();
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let contents = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Deserializer};
+use tui::style::Color;
+
+/// Colors used by the interface, set under `theme` in the config
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Theme {
+    /// Colors of the thread gutters, one per depth level, starting over from
+    /// the first once the thread is deeper than the list is long. Read once
+    /// when the config is loaded
+    #[serde(deserialize_with = "thread_colors")]
+    pub thread_colors: Vec<Color>
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            thread_colors: vec![Color::Blue, Color::Green, Color::Yellow, Color::Magenta, Color::Cyan, Color::Red]
+        }
+    }
+}
+
+/// The thread colors that could be read, falling back to the default ones
+/// when none could
+fn thread_colors<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Color>, D::Error> {
+    let names = Vec::<String>::deserialize(deserializer)?;
+    let palette: Vec<Color> = names
+        .iter()
+        .filter_map(|name| {
+            let color = parse_color(name);
+            if color.is_none() {
+                log::warn!("unknown thread color {}", name);
+            }
+            color
+        })
+        .collect();
+
+    if palette.is_empty() {
+        Ok(Theme::default().thread_colors)
+    } else {
+        Ok(palette)
+    }
+}
+
+/// Reads a color given by name, e.g. `lightblue`, as `#rrggbb`, or as an index
+/// into the terminal's 256 colors
+pub fn parse_color(name: &str) -> Option<Color> {
+    let name = name.trim().to_lowercase().replace(&['-', '_', ' '][..], "");
+
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(index) = name.parse::<u8>() {
+        return Some(Color::Indexed(index));
+    }
+
+    let color = match name.as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None
+    };
+
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_reads_names_hex_and_indices() {
+        assert_eq!(parse_color("Light-Blue"), Some(Color::LightBlue));
+        assert_eq!(parse_color("dark grey"), Some(Color::DarkGray));
+        assert_eq!(parse_color("#ff8000"), Some(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("208"), Some(Color::Indexed(208)));
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("#gg0000"), None);
+        assert_eq!(parse_color("orange"), None);
+    }
+
+    #[test]
+    fn unknown_thread_colors_are_left_out() {
+        let theme: Theme = serde_json::from_str(r##"{ "thread_colors": ["red", "orange", "#00ff00"] }"##).unwrap();
+
+        assert_eq!(theme.thread_colors, [Color::Red, Color::Rgb(0, 255, 0)]);
+    }
+
+    #[test]
+    fn thread_colors_fall_back_to_the_defaults() {
+        let theme: Theme = serde_json::from_str(r#"{ "thread_colors": ["orange"] }"#).unwrap();
+        assert_eq!(theme.thread_colors, Theme::default().thread_colors);
+
+        let theme: Theme = serde_json::from_str("{}").unwrap();
+        assert_eq!(theme.thread_colors, Theme::default().thread_colors);
+    }
+}
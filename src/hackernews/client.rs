@@ -0,0 +1,473 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant}
+};
+
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use serde::de::DeserializeOwned;
+use tokio::sync::Semaphore;
+
+pub const HACKERNEWS_API: &str = "https://hacker-news.firebaseio.com/v0";
+
+/// Items kept in the cache, beyond which the least recently used are dropped.
+/// Enough for a few large threads
+const MAX_CACHED_ITEMS: usize = 5000;
+
+/// Bounds on how hard the API is hit
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Requests allowed to be in flight at once
+    pub max_concurrent: usize,
+
+    /// Sustained request rate, with bursts of up to a second's worth of
+    /// requests. A rate of 0 disables rate limiting
+    pub requests_per_second: f64,
+
+    pub timeout: Duration,
+
+    /// Times a request is retried after a timeout, a 429 or a 5xx
+    pub retries: u32
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_concurrent: 8,
+            requests_per_second: 20.0,
+            timeout: Duration::from_secs(10),
+            retries: 3
+        }
+    }
+}
+
+/// Token bucket refilled at a constant rate. Each request takes a token,
+/// waiting for one to be refilled when the bucket is empty
+struct RateLimiter {
+    rate: f64,
+    burst: f64,
+
+    /// Tokens left and when they were last refilled
+    bucket: Mutex<(f64, Instant)>
+}
+
+impl RateLimiter {
+    fn new(rate: f64) -> RateLimiter {
+        let burst = rate.max(1.0);
+        RateLimiter {
+            rate,
+            burst,
+            bucket: Mutex::new((burst, Instant::now()))
+        }
+    }
+
+    async fn acquire(&self) {
+        if self.rate <= 0.0 {
+            return;
+        }
+
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let (tokens, refilled) = *bucket;
+                let tokens = (tokens + refilled.elapsed().as_secs_f64() * self.rate).min(self.burst);
+
+                if tokens >= 1.0 {
+                    *bucket = (tokens - 1.0, Instant::now());
+                    return;
+                }
+                *bucket = (tokens, Instant::now());
+
+                Duration::from_secs_f64((1.0 - tokens) / self.rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Delay before retrying a request, doubling with every attempt and jittered
+/// so that requests that failed together don't all retry together
+fn backoff(attempt: u32) -> Duration {
+    let base = Duration::from_millis(250) * 2u32.pow(attempt.min(5));
+    let jitter = rand::thread_rng().gen_range(0, 250);
+    base + Duration::from_millis(jitter)
+}
+
+/// Raw item json by id, dropping the least recently used items once it holds
+/// more than its capacity
+struct ItemCache {
+    capacity: usize,
+
+    /// Items along with when they were last used
+    items: HashMap<i32, (serde_json::Value, u64)>,
+
+    /// Counts up with every use
+    clock: u64
+}
+
+impl ItemCache {
+    fn new(capacity: usize) -> ItemCache {
+        ItemCache {
+            capacity,
+            items: HashMap::new(),
+            clock: 0
+        }
+    }
+
+    fn get(&mut self, id: i32) -> Option<serde_json::Value> {
+        self.clock += 1;
+        let (value, used) = self.items.get_mut(&id)?;
+        *used = self.clock;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, id: i32, value: serde_json::Value) {
+        self.clock += 1;
+        self.items.insert(id, (value, self.clock));
+
+        if self.items.len() > self.capacity {
+            let oldest = self.items.iter().min_by_key(|(_, (_, used))| *used).map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                self.items.remove(&oldest);
+            }
+        }
+    }
+
+    fn remove(&mut self, id: i32) {
+        self.items.remove(&id);
+    }
+}
+
+/// HTTP client shared by every request made to the Hacker News API
+pub struct Client {
+    http: reqwest::Client,
+
+    /// Streams stay open indefinitely, so they use a client without a timeout
+    stream_http: reqwest::Client,
+
+    api_url: String,
+
+    limits: Limits,
+
+    /// Bounds the number of requests in flight
+    semaphore: Semaphore,
+
+    rate_limiter: RateLimiter,
+
+    /// Items are only cached while live updates are enabled, since those are
+    /// what invalidate the entries that changed
+    cache: Option<Mutex<ItemCache>>
+}
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Sets up the shared client, failing when the http client can't be built
+/// with the limits. Has no effect once the client has been used
+pub fn init(api_url: &str, cache_items: bool, limits: Limits) -> Result<(), Box<dyn Error>> {
+    let _ = CLIENT.set(Client::new(api_url, cache_items, limits)?);
+    Ok(())
+}
+
+pub fn client() -> &'static Client {
+    // Building a client only fails when TLS can't be set up, which
+    // `reqwest::Client::new` panics on as well
+    CLIENT.get_or_init(|| Client::new(HACKERNEWS_API, false, Limits::default()).expect("failed to set up the http client"))
+}
+
+impl Client {
+    pub fn new(api_url: &str, cache_items: bool, limits: Limits) -> Result<Client, Box<dyn Error>> {
+        let http = reqwest::Client::builder()
+            .timeout(limits.timeout)
+            .build()?;
+
+        Ok(Client {
+            http,
+            stream_http: reqwest::Client::builder().build()?,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            semaphore: Semaphore::new(limits.max_concurrent.max(1)),
+            rate_limiter: RateLimiter::new(limits.requests_per_second),
+            limits,
+            cache: if cache_items { Some(Mutex::new(ItemCache::new(MAX_CACHED_ITEMS))) } else { None }
+        })
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.api_url, path)
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, Box<dyn Error>> {
+        let body = self.get_text(&self.url(path)).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Fetches a url within the concurrency and rate limits, retrying with
+    /// exponential backoff when the request times out or the server is
+    /// overloaded
+    pub async fn get_text(&self, url: &str) -> Result<String, Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let _permit = self.semaphore.acquire().await?;
+                self.rate_limiter.acquire().await;
+                self.send(url).await
+            };
+
+            let error = match result {
+                Ok(body) => return Ok(body),
+                Err(error) => error
+            };
+
+            let retry_after = match &error {
+                RequestError::Status(status, retry_after) if *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() => *retry_after,
+                RequestError::Http(http_error) if http_error.is_timeout() || http_error.is_connect() => None,
+                _ => return Err(error.into())
+            };
+
+            if attempt >= self.limits.retries {
+                return Err(error.into());
+            }
+
+            let delay = retry_after.unwrap_or_else(|| backoff(attempt));
+            log::warn!("retrying {} in {:?}", url, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send(&self, url: &str) -> Result<String, RequestError> {
+        let response = self.http.get(url).send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            // The server may say how long to wait before trying again
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            return Err(RequestError::Status(status, retry_after));
+        }
+
+        Ok(response.text().await?)
+    }
+
+    /// Opens a streaming request for a path, the response body being a
+    /// `text/event-stream` of changes to the data at that path
+    pub async fn stream(&self, path: &str) -> Result<reqwest::Response, Box<dyn Error>> {
+        let response = self.stream_http
+            .get(self.url(path))
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response)
+    }
+
+    /// Fetches an item by id, serving it from the cache when possible
+    pub async fn get_item<T: DeserializeOwned>(&self, id: i32) -> Result<T, Box<dyn Error>> {
+        if let Some(cache) = &self.cache {
+            let cached = cache.lock().unwrap().get(id);
+            if let Some(value) = cached {
+                return Ok(serde_json::from_value(value)?);
+            }
+        }
+
+        let value: serde_json::Value = self.get(&format!("item/{}.json", id)).await?;
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap().insert(id, value.clone());
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Drops cached items so that they are refetched the next time they're used
+    pub fn invalidate(&self, ids: &[i32]) {
+        if let Some(cache) = &self.cache {
+            let mut cache = cache.lock().unwrap();
+            for id in ids {
+                cache.remove(*id);
+            }
+        }
+    }
+}
+
+/// Why a single attempt at a request failed
+#[derive(Debug)]
+enum RequestError {
+    Http(reqwest::Error),
+    Status(StatusCode, Option<Duration>)
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RequestError::Http(error) => write!(f, "{}", error),
+            RequestError::Status(status, _) => write!(f, "server responded with {}", status)
+        }
+    }
+}
+
+impl Error for RequestError {}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(error: reqwest::Error) -> RequestError {
+        RequestError::Http(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc
+        },
+        thread
+    };
+
+    use super::*;
+
+    /// Answers requests with the given responses in turn, the last one
+    /// repeating. Returns the url and the number of requests received
+    fn serve(responses: Vec<&'static str>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let received = count.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(x) => x,
+                    Err(_) => return
+                };
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read])
+                    }
+                }
+
+                let index = received.fetch_add(1, Ordering::SeqCst).min(responses.len() - 1);
+                let _ = stream.write_all(responses[index].as_bytes());
+            }
+        });
+
+        (url, count)
+    }
+
+    const UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const ITEM: &str = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 9\r\nConnection: close\r\n\r\n{\"id\":42}";
+
+    #[tokio::test]
+    async fn unavailable_server_is_retried() {
+        let (url, count) = serve(vec![UNAVAILABLE, ITEM]);
+        let client = Client::new(&url, false, Limits::default()).unwrap();
+
+        let item: serde_json::Value = client.get_item(42).await.unwrap();
+
+        assert_eq!(item, serde_json::json!({"id": 42}));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_give_up_eventually() {
+        let (url, count) = serve(vec![UNAVAILABLE]);
+        let limits = Limits {
+            retries: 2,
+            ..Limits::default()
+        };
+        let client = Client::new(&url, false, limits).unwrap();
+
+        let error = client.get_item::<serde_json::Value>(42).await.unwrap_err();
+
+        assert!(error.to_string().contains("503"));
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn missing_item_is_not_retried() {
+        let (url, count) = serve(vec![NOT_FOUND, ITEM]);
+        let client = Client::new(&url, false, Limits::default()).unwrap();
+
+        let error = client.get_item::<serde_json::Value>(42).await.unwrap_err();
+
+        assert!(error.to_string().contains("404"));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        for _ in 0..20 {
+            let first = backoff(0);
+            assert!(first >= Duration::from_millis(250) && first < Duration::from_millis(500));
+
+            let third = backoff(2);
+            assert!(third >= Duration::from_millis(1000) && third < Duration::from_millis(1250));
+
+            assert!(backoff(30) < Duration::from_millis(8250));
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_allows_a_burst_then_waits() {
+        let limiter = RateLimiter::new(20.0);
+
+        let started = Instant::now();
+        for _ in 0..20 {
+            limiter.acquire().await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        // Two more tokens take a twentieth of a second each to refill
+        for _ in 0..2 {
+            limiter.acquire().await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_can_be_disabled() {
+        let limiter = RateLimiter::new(0.0);
+
+        let started = Instant::now();
+        for _ in 0..1000 {
+            limiter.acquire().await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn cache_drops_least_recently_used() {
+        let mut cache = ItemCache::new(2);
+        cache.insert(1, serde_json::json!(1));
+        cache.insert(2, serde_json::json!(2));
+
+        // Using 1 leaves 2 as the oldest
+        assert!(cache.get(1).is_some());
+        cache.insert(3, serde_json::json!(3));
+
+        assert_eq!(cache.items.len(), 2);
+        assert!(cache.get(2).is_none());
+        assert_eq!(cache.get(1), Some(serde_json::json!(1)));
+        assert_eq!(cache.get(3), Some(serde_json::json!(3)));
+    }
+
+    #[test]
+    fn cache_removes_invalidated() {
+        let mut cache = ItemCache::new(2);
+        cache.insert(1, serde_json::json!(1));
+        cache.remove(1);
+
+        assert!(cache.get(1).is_none());
+    }
+}
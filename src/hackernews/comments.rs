@@ -1,10 +1,21 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use futures::stream::{FuturesUnordered, StreamExt};
 use regex::Regex;
 
+use crate::hackernews::client::client;
+
 #[derive(serde::Deserialize, Debug, Clone)]
 pub struct Comment {
     #[serde(default)]
     pub by: String,
 
+    #[serde(default)]
+    pub deleted: bool,
+
+    #[serde(default)]
+    pub dead: bool,
+
     #[serde(default)]
     pub id: i32,
 
@@ -17,6 +28,11 @@ pub struct Comment {
     #[serde(default)]
     pub depth: i32,
 
+    /// Position among the replies to the same parent, in the order the site
+    /// ranks them
+    #[serde(default)]
+    pub rank: usize,
+
     #[serde(default)]
     pub parent: i32,
 
@@ -26,17 +42,41 @@ pub struct Comment {
     #[serde(default)]
     pub time: u32,
 
+    /// Whether the comments in `kids` have been fetched yet
+    #[serde(default)]
+    pub replies_loaded: bool,
+
+    /// Whether the replies are below the depth loaded up front, and are only
+    /// fetched once they're asked for
     #[serde(default)]
-    pub r#type: String
+    pub replies_deferred: bool
 }
 
-async fn get_comment(comment_id: i32) -> Comment {
-    reqwest::get(format!("https://hacker-news.firebaseio.com/v0/item/{}.json", comment_id))
-        .await
-        .unwrap()
-        .json::<Comment>()
+impl Comment {
+    /// Text to show for the comment, with a placeholder standing in for the
+    /// text of deleted comments, and of dead comments unless `show_dead` is set
+    pub fn display_text(&self, show_dead: bool) -> String {
+        if self.deleted {
+            String::from("[deleted]")
+        } else if self.dead && !show_dead {
+            String::from("[dead]")
+        } else if self.dead {
+            format!("[dead] {}", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+
+    /// Deleted comments are only worth showing when they hold replies in place
+    fn is_hidden(&self) -> bool {
+        self.deleted && self.kids.is_empty()
+    }
+}
+
+pub async fn get_comment(comment_id: i32) -> Result<Comment, Box<dyn std::error::Error>> {
+    client()
+        .get_item::<Comment>(comment_id)
         .await
-        .unwrap()
 }
 
 pub fn flatten_comments(comments: &Vec<Comment>) -> Vec<Comment>{
@@ -44,7 +84,7 @@ pub fn flatten_comments(comments: &Vec<Comment>) -> Vec<Comment>{
 
     for comment in comments {
         flat_comments.push(comment.clone());
-        if comment.kids.len() > 0 {
+        if !comment.kids.is_empty() {
             let kid_comments = flatten_comments(&comment.kid_comments);
             for kid_comment in kid_comments {
                 flat_comments.push(kid_comment);
@@ -55,8 +95,121 @@ pub fn flatten_comments(comments: &Vec<Comment>) -> Vec<Comment>{
     flat_comments
 }
 
+/// Orders in which the replies to each comment can be shown
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortOrder {
+    /// The order of `kids`, which is how the site ranks them
+    Ranked,
+    Newest,
+    Oldest,
+    MostReplies,
+    /// Comments by the author of the story first, the rest ranked
+    OpFirst
+}
+
+impl SortOrder {
+    /// The order after this one, for cycling through them
+    pub fn next(self) -> SortOrder {
+        match self {
+            SortOrder::Ranked => SortOrder::Newest,
+            SortOrder::Newest => SortOrder::Oldest,
+            SortOrder::Oldest => SortOrder::MostReplies,
+            SortOrder::MostReplies => SortOrder::OpFirst,
+            SortOrder::OpFirst => SortOrder::Ranked
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Ranked => "ranked",
+            SortOrder::Newest => "newest first",
+            SortOrder::Oldest => "oldest first",
+            SortOrder::MostReplies => "most replies",
+            SortOrder::OpFirst => "OP first"
+        }
+    }
+}
+
+/// Sorts comments, and the replies of each of them, by `order`. `op` is the
+/// author of the story, for putting their comments first
+pub fn sort_comments(comments: &mut [Comment], order: SortOrder, op: &str) {
+    match order {
+        SortOrder::Ranked => comments.sort_by_key(|comment| comment.rank),
+        SortOrder::Newest => comments.sort_by_key(|comment| Reverse(comment.time)),
+        SortOrder::Oldest => comments.sort_by_key(|comment| comment.time),
+        SortOrder::MostReplies => comments.sort_by_key(|comment| Reverse(comment.kids.len())),
+        // Without an author, e.g. when a comment is opened on its own, deleted
+        // comments would otherwise match, having no author either
+        SortOrder::OpFirst => comments.sort_by_key(|comment| (op.is_empty() || comment.by != op, comment.rank))
+    }
+
+    for comment in comments {
+        sort_comments(&mut comment.kid_comments, order, op);
+    }
+}
+
+/// Sorts an already flattened thread, keeping every comment directly above
+/// its replies
+pub fn sort_flat(comments: Vec<Comment>, order: SortOrder, op: &str) -> Vec<Comment> {
+    // Rebuilding the tree from the depths, since each comment is followed by
+    // the comments below it
+    let mut roots: Vec<Comment> = Vec::new();
+    let mut stack: Vec<Comment> = Vec::new();
+    for mut comment in comments {
+        comment.kid_comments.clear();
+        while stack.last().is_some_and(|top| top.depth >= comment.depth) {
+            let done = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some(parent) => parent.kid_comments.push(done),
+                None => roots.push(done)
+            }
+        }
+        stack.push(comment);
+    }
+    while let Some(done) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.kid_comments.push(done),
+            None => roots.push(done)
+        }
+    }
+
+    sort_comments(&mut roots, order, op);
+
+    let mut flat = Vec::new();
+    take_flattened(roots, &mut flat);
+    flat
+}
+
+/// Like `flatten_comments`, but moving the replies out of each comment
+/// instead of cloning them along with it
+fn take_flattened(comments: Vec<Comment>, flat: &mut Vec<Comment>) {
+    for mut comment in comments {
+        let kid_comments = std::mem::take(&mut comment.kid_comments);
+        flat.push(comment);
+        take_flattened(kid_comments, flat);
+    }
+}
+
+/// Marks the replies of comments at or below `max_depth` as deferred, so that
+/// they aren't fetched until asked for
+pub fn defer_replies(comments: &mut [Comment], max_depth: Option<i32>) {
+    let max_depth = match max_depth {
+        Some(x) => x,
+        None => return
+    };
+
+    for comment in comments {
+        if !comment.kids.is_empty() && comment.depth + 1 >= max_depth {
+            comment.replies_deferred = true;
+        }
+    }
+}
+
+/// Fetches comments along with all of their replies. When a `max_depth` is
+/// given, replies at that depth and below are deferred instead, except for
+/// the replies to the `expanded` comments, which were already asked for
 #[async_recursion::async_recursion]
-pub async fn get_comments(comment_parents: &Vec<i32>, depth: i32) -> Result<Vec<Comment>, Box<dyn std::error::Error>> {
+pub async fn get_comments(comment_parents: &Vec<i32>, depth: i32, max_depth: Option<i32>, expanded: &HashSet<i32>) -> Result<Vec<Comment>, Box<dyn std::error::Error>> {
     let mut comments: Vec<Comment> = Vec::new();
 
     let mut comments_futures = Vec::new();
@@ -65,26 +218,26 @@ pub async fn get_comments(comment_parents: &Vec<i32>, depth: i32) -> Result<Vec<
         comments_futures.push(comment);
     }
 
-    let mut futures = futures::future::join_all(comments_futures).await;
+    let mut futures = futures::future::try_join_all(comments_futures).await?;
 
-    for comment in &mut futures {
-        // Unsure why, but occasionally we are getting empty users
-        if comment.by == "" {
+    for (rank, comment) in futures.iter_mut().enumerate() {
+        if comment.is_hidden() {
             continue;
         }
+        comment.rank = rank;
 
         // Cleaning up string to displaying purposes
-        comment.text = decode_comments(&comment.text);
-        comment.text = remove_html_tags(&comment.text);
+        comment.text = clean_text(&comment.text);
 
         comment.depth = depth;
-        if comment.kids.len() > 0 {
+        defer_replies(std::slice::from_mut(comment), max_depth);
+        if expanded.contains(&comment.id) {
+            comment.replies_deferred = false;
+        }
+        comment.replies_loaded = !comment.replies_deferred;
+        if comment.replies_loaded && !comment.kids.is_empty() {
             let depth = depth + 1;
-            let kid_comments = get_comments(&comment.kids, depth).await;
-            comment.kid_comments = match kid_comments {
-                Ok(x) => x,
-                Err(error) => panic!("{}", error)
-            };
+            comment.kid_comments = get_comments(&comment.kids, depth, max_depth, expanded).await?;
         }
 
         comments.push(comment.clone());
@@ -93,6 +246,70 @@ pub async fn get_comments(comment_parents: &Vec<i32>, depth: i32) -> Result<Vec<
     Ok(comments)
 }
 
+/// Decodes the HTML entities and strips the markup that the API returns in
+/// comment and profile text
+pub fn clean_text(text: &str) -> String {
+    remove_html_tags(&decode_comments(text))
+}
+
+/// Fetches comments without their replies, which are left to be loaded by
+/// `load_replies`. Comments that fail to load are skipped
+pub async fn get_comments_shallow(comment_ids: &[i32], depth: i32) -> Vec<Comment> {
+    let comment_futures = comment_ids.iter().enumerate().map(|(rank, comment_id)| async move {
+        match client().get_item::<Comment>(*comment_id).await {
+            Ok(mut comment) => {
+                comment.rank = rank;
+                Some(comment)
+            }
+            Err(error) => {
+                log::error!("failed to load comment {}: {}", comment_id, error);
+                None
+            }
+        }
+    });
+
+    futures::future::join_all(comment_futures)
+        .await
+        .into_iter()
+        .flatten()
+        .filter(|comment| !comment.is_hidden())
+        .map(|mut comment| {
+            comment.text = clean_text(&comment.text);
+            comment.depth = depth;
+            comment
+        })
+        .collect()
+}
+
+/// Loads the replies below the given comments, breadth first, down to
+/// `max_depth` when one is given. `on_replies` is called with the id of a
+/// comment and its replies as soon as they arrive, so that a thread can be
+/// shown while its deeper levels are still loading
+pub async fn load_replies<F: FnMut(i32, Vec<Comment>)>(comments: &[Comment], max_depth: Option<i32>, mut on_replies: F) {
+    let mut pending = FuturesUnordered::new();
+    for comment in comments {
+        if !comment.kids.is_empty() && !comment.replies_deferred {
+            pending.push(load_level(comment.id, comment.kids.clone(), comment.depth + 1));
+        }
+    }
+
+    // Replies are queued as their parents arrive. Requests are served in the
+    // order they are made, so shallower levels are fetched first
+    while let Some((parent, mut replies)) = pending.next().await {
+        defer_replies(&mut replies, max_depth);
+        for reply in &replies {
+            if !reply.kids.is_empty() && !reply.replies_deferred {
+                pending.push(load_level(reply.id, reply.kids.clone(), reply.depth + 1));
+            }
+        }
+        on_replies(parent, replies);
+    }
+}
+
+async fn load_level(parent: i32, comment_ids: Vec<i32>, depth: i32) -> (i32, Vec<Comment>) {
+    (parent, get_comments_shallow(&comment_ids, depth).await)
+}
+
 fn remove_html_tags(text: &str) -> String {
     // Removing paragraph tags which typically seem to appear immediately after a period
     let re = Regex::new(r"<[p].*?>").unwrap();
@@ -125,9 +342,106 @@ fn remove_html_tags(text: &str) -> String {
     text
 }
 
+/// Decodes HTML entities, leaving the text as it is when it can't be decoded,
+/// e.g. because it holds a bare `&`
 fn decode_comments(text: &str) -> String {
     match htmlescape::decode_html(text) {
         Ok(text) => text,
-        Err(error) => panic!("{:?}", error)
+        Err(error) => {
+            log::warn!("failed to decode entities ({:?}), showing the raw text", error);
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_text_decodes_entities() {
+        assert_eq!(clean_text("Tom &amp; Jerry&#x27;s"), "Tom & Jerry's");
+    }
+
+    #[test]
+    fn clean_text_keeps_bare_ampersands() {
+        assert_eq!(clean_text("a && b"), "a && b");
+        assert_eq!(clean_text("AT&T"), "AT&T");
+    }
+
+    fn comment(id: i32, depth: i32, rank: usize, by: &str, time: u32, kids: usize) -> Comment {
+        Comment {
+            by: by.to_string(),
+            deleted: by.is_empty(),
+            dead: false,
+            id,
+            kids: (0..kids as i32).collect(),
+            kid_comments: Vec::new(),
+            depth,
+            rank,
+            parent: 0,
+            text: String::new(),
+            time,
+            replies_loaded: true,
+            replies_deferred: false
+        }
+    }
+
+    /// Two top level comments, the first with a reply that has a reply of
+    /// its own and a second reply, flattened in the ranked order
+    fn thread() -> Vec<Comment> {
+        vec![
+            comment(1, 0, 0, "alice", 100, 2),
+            comment(2, 1, 0, "bob", 300, 1),
+            comment(3, 2, 0, "carol", 400, 0),
+            comment(4, 1, 1, "op", 200, 0),
+            comment(5, 0, 1, "op", 500, 0),
+        ]
+    }
+
+    fn ids(comments: &[Comment]) -> Vec<i32> {
+        comments.iter().map(|comment| comment.id).collect()
+    }
+
+    #[test]
+    fn ranked_keeps_the_thread_as_it_is() {
+        assert_eq!(ids(&sort_flat(thread(), SortOrder::Ranked, "op")), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn newest_sorts_each_level_and_keeps_replies_below_their_parent() {
+        let sorted = sort_flat(thread(), SortOrder::Newest, "op");
+
+        assert_eq!(ids(&sorted), vec![5, 1, 2, 3, 4]);
+        assert_eq!(sorted.iter().map(|comment| comment.depth).collect::<Vec<_>>(), vec![0, 0, 1, 2, 1]);
+    }
+
+    #[test]
+    fn oldest_sorts_each_level() {
+        assert_eq!(ids(&sort_flat(thread(), SortOrder::Oldest, "op")), vec![1, 4, 2, 3, 5]);
+    }
+
+    #[test]
+    fn most_replies_sorts_each_level() {
+        let mut comments = thread();
+        comments[3].kids = vec![10, 11, 12];
+
+        assert_eq!(ids(&sort_flat(comments, SortOrder::MostReplies, "op")), vec![1, 4, 2, 3, 5]);
+    }
+
+    #[test]
+    fn op_first_moves_the_authors_comments_up_at_each_level() {
+        assert_eq!(ids(&sort_flat(thread(), SortOrder::OpFirst, "op")), vec![5, 1, 4, 2, 3]);
+    }
+
+    #[test]
+    fn op_first_without_an_author_keeps_deleted_comments_in_place() {
+        let mut comments = thread();
+        comments[4] = comment(5, 0, 1, "", 500, 0);
+        comments.insert(0, comment(6, 0, 0, "dave", 50, 0));
+        comments[1].rank = 1;
+        comments[5].rank = 2;
+
+        assert_eq!(ids(&sort_flat(comments, SortOrder::OpFirst, "")), vec![6, 1, 2, 3, 4, 5]);
     }
 }
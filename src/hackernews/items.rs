@@ -0,0 +1,114 @@
+use std::error::Error;
+
+use crate::hackernews::{client::client, stories::Story};
+
+/// The fields shared by every kind of item, enough to tell what an item is
+/// and where it sits in a thread
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct Item {
+    #[serde(default)]
+    pub id: i32,
+
+    /// One of `story`, `comment`, `job`, `poll` or `pollopt`
+    #[serde(default)]
+    pub r#type: String,
+
+    /// Comment or story that a comment replies to
+    #[serde(default)]
+    pub parent: i32,
+
+    /// Poll that a poll option belongs to
+    #[serde(default)]
+    pub poll: i32
+}
+
+/// Reads an item id from either a bare number or a link to the item on the
+/// website, e.g. `https://news.ycombinator.com/item?id=8863`. Links to other
+/// pages, such as a user's, aren't taken for items
+pub fn parse_item_id(input: &str) -> Option<i32> {
+    let input = input.trim();
+    if input.chars().all(|c| c.is_ascii_digit()) {
+        return input.parse::<i32>().ok().filter(|id| *id > 0);
+    }
+
+    let (page, query) = input.split_once('?')?;
+    if page != "item" && !page.ends_with("/item") {
+        return None;
+    }
+
+    let query = query.split('#').next().unwrap_or_default();
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("id="))
+        .filter(|id| id.chars().all(|c| c.is_ascii_digit()))
+        .and_then(|id| id.parse::<i32>().ok())
+        .filter(|id| *id > 0)
+}
+
+pub async fn get_item(id: i32) -> Result<Item, Box<dyn Error>> {
+    // Ids that don't exist yet are returned as null
+    match client().get_item::<Option<Item>>(id).await? {
+        Some(item) => Ok(item),
+        None => Err(format!("no item with id {}", id).into())
+    }
+}
+
+/// Walks up the thread from an item to the story, poll or job at its root.
+/// Returns the root along with the ids of the comments leading from the top
+/// level of its thread down to the item, which is empty when the item is the
+/// root itself
+pub async fn find_root(id: i32) -> Result<(Story, Vec<i32>), Box<dyn Error>> {
+    let mut path = Vec::new();
+    let mut item = get_item(id).await?;
+
+    loop {
+        match item.r#type.as_str() {
+            "comment" => {
+                path.push(item.id);
+                item = get_item(item.parent).await?;
+            }
+            // Options are shown with the poll they belong to
+            "pollopt" => item = get_item(item.poll).await?,
+            _ => break
+        }
+    }
+
+    let story = client().get_item::<Story>(item.id).await?;
+    path.reverse();
+
+    Ok((story, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_ids() {
+        assert_eq!(parse_item_id("8863"), Some(8863));
+        assert_eq!(parse_item_id(" 8863 \n"), Some(8863));
+        assert_eq!(parse_item_id("0"), None);
+        assert_eq!(parse_item_id("-5"), None);
+        assert_eq!(parse_item_id("+5"), None);
+        assert_eq!(parse_item_id("99999999999"), None);
+        assert_eq!(parse_item_id(""), None);
+    }
+
+    #[test]
+    fn item_links() {
+        assert_eq!(parse_item_id("https://news.ycombinator.com/item?id=8863"), Some(8863));
+        assert_eq!(parse_item_id("news.ycombinator.com/item?id=8863#8900"), Some(8863));
+        assert_eq!(parse_item_id("https://news.ycombinator.com/item?p=2&id=8863"), Some(8863));
+        assert_eq!(parse_item_id("item?id=8863"), Some(8863));
+    }
+
+    #[test]
+    fn other_links() {
+        assert_eq!(parse_item_id("https://example.com/?id=5"), None);
+        assert_eq!(parse_item_id("news.ycombinator.com/user?id=123"), None);
+        assert_eq!(parse_item_id("https://news.ycombinator.com/item?id=-5"), None);
+        assert_eq!(parse_item_id("https://news.ycombinator.com/item?id=5x"), None);
+        assert_eq!(parse_item_id("https://news.ycombinator.com/item?uid=5"), None);
+        assert_eq!(parse_item_id("https://news.ycombinator.com/item"), None);
+    }
+}
@@ -1,2 +1,9 @@
+pub mod client;
 pub mod stories;
 pub mod comments;
+pub mod polls;
+pub mod items;
+pub mod users;
+pub mod updates;
+pub mod stream;
+pub mod session;
@@ -0,0 +1,34 @@
+use crate::hackernews::client::client;
+
+/// One of the options of a poll, listed in the poll's `parts`
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct PollOption {
+    #[serde(default)]
+    pub score: i32,
+
+    #[serde(default)]
+    pub text: String
+}
+
+/// Fetches the options of a poll, keeping their order. Options that fail to
+/// load are skipped
+pub async fn get_poll_options(parts: &[i32]) -> Vec<PollOption> {
+    let option_futures = parts.iter().map(|option_id| async move {
+        match client().get_item::<PollOption>(*option_id).await {
+            Ok(mut option) => {
+                option.text = crate::hackernews::comments::clean_text(&option.text);
+                Some(option)
+            }
+            Err(error) => {
+                log::error!("failed to load poll option {}: {}", option_id, error);
+                None
+            }
+        }
+    });
+
+    futures::future::join_all(option_futures)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
@@ -0,0 +1,560 @@
+use std::{error::Error, fmt, time::Duration};
+
+use regex::Regex;
+use reqwest::{header, redirect, StatusCode};
+use scraper::{node::Node, Html};
+
+use crate::hackernews::stories::Feed;
+
+/// The website, which unlike the API accepts actions on behalf of an account
+pub const HACKERNEWS_SITE: &str = "https://news.ycombinator.com";
+
+/// Returned when the website no longer recognizes the session cookie, after
+/// which logging in again is needed
+#[derive(Debug)]
+pub struct LoggedOut;
+
+impl fmt::Display for LoggedOut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not logged in")
+    }
+}
+
+impl Error for LoggedOut {}
+
+/// Something done on behalf of the logged in account
+pub enum Action {
+    Upvote(i32),
+    Unvote(i32),
+    Favorite(i32),
+    Unfavorite(i32),
+    Reply {
+        parent: i32,
+        text: String
+    },
+    /// A link when `url` is set, otherwise a text post such as an Ask HN
+    Submit {
+        title: String,
+        url: String,
+        text: String
+    }
+}
+
+/// A logged in account on the website. Actions are taken by following the
+/// links on item pages, which carry the auth token that the website expects
+pub struct Session {
+    site_url: String,
+    http: reqwest::Client,
+    pub username: String,
+
+    /// Value of the `user` cookie set by the website on login
+    pub cookie: String
+}
+
+impl Session {
+    /// Picks up a session from a cookie saved by an earlier login
+    pub fn new(site_url: &str, username: &str, cookie: &str) -> Session {
+        Session {
+            site_url: site_url.trim_end_matches('/').to_string(),
+            http: http_client(),
+            username: username.to_string(),
+            cookie: cookie.to_string()
+        }
+    }
+
+    pub async fn login(site_url: &str, username: &str, password: &str) -> Result<Session, Box<dyn Error>> {
+        let session = Session::new(site_url, username, "");
+        let response = session
+            .http
+            .post(session.url("login"))
+            .form(&[("acct", username), ("pw", password), ("goto", "news")])
+            .send()
+            .await?;
+
+        // A successful login redirects with the cookie set, while a failed one
+        // shows the login form again
+        let cookie = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| value.split(';').next())
+            .find_map(|pair| pair.strip_prefix("user="))
+            .map(String::from);
+
+        match cookie {
+            Some(cookie) => Ok(Session { cookie, ..session }),
+            None => Err(format!("login failed for {}", username).into())
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.site_url, path)
+    }
+
+    /// Fetches a page of the website as the logged in account
+    async fn get_page(&self, path: &str) -> Result<String, Box<dyn Error>> {
+        let response = self
+            .http
+            .get(self.url(path))
+            .header(header::COOKIE, format!("user={}", self.cookie))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("{} returned {}", path, status).into());
+        }
+
+        let page = response.text().await?;
+        if !is_logged_in(&page) {
+            return Err(Box::new(LoggedOut));
+        }
+
+        Ok(page)
+    }
+
+    /// Follows a link that takes an action. The website answers those with a
+    /// redirect back to where the action was taken from
+    async fn follow(&self, link: &str) -> Result<(), Box<dyn Error>> {
+        let response = self
+            .http
+            .get(self.url(link))
+            .header(header::COOKIE, format!("user={}", self.cookie))
+            .send()
+            .await?;
+
+        match response.status() {
+            status if status.is_success() || status.is_redirection() => Ok(()),
+            StatusCode::FORBIDDEN => Err(Box::new(LoggedOut)),
+            status => Err(format!("{} returned {}", link, status).into())
+        }
+    }
+
+    /// Posts a form, which the website answers with a redirect when it's
+    /// accepted and with a page explaining why when it isn't
+    async fn post_form(&self, path: &str, form: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+        let response = self
+            .http
+            .post(self.url(path))
+            .header(header::COOKIE, format!("user={}", self.cookie))
+            .form(form)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_redirection() {
+            return Ok(());
+        }
+
+        let page = response.text().await?;
+        let reason: String = page_text(&page).chars().take(100).collect();
+        if reason.is_empty() {
+            Err(format!("{} returned {}", path, status).into())
+        } else {
+            Err(reason.into())
+        }
+    }
+
+    pub async fn perform(&self, action: &Action) -> Result<(), Box<dyn Error>> {
+        match action {
+            Action::Upvote(id) => self.follow_action(*id, "vote", &[("how", Some("up"))]).await,
+            Action::Unvote(id) => self.follow_action(*id, "vote", &[("how", Some("un"))]).await,
+            Action::Favorite(id) => self.follow_action(*id, "fave", &[("un", None)]).await,
+            Action::Unfavorite(id) => self.follow_action(*id, "fave", &[("un", Some("t"))]).await,
+            Action::Reply { parent, text } => self.reply(*parent, text).await,
+            Action::Submit { title, url, text } => self.submit(title, url, text).await
+        }
+    }
+
+    /// Follows the link for an action from the page of the item
+    async fn follow_action(&self, id: i32, action: &str, params: &[(&str, Option<&str>)]) -> Result<(), Box<dyn Error>> {
+        let page = self.get_page(&format!("item?id={}", id)).await?;
+        match find_link(&page, action, id, params) {
+            Some(link) => self.follow(&link).await,
+            // Items that are too old, or already voted on or favorited, have no link
+            None => Err(format!("no {} link on the page of item {}", action, id).into())
+        }
+    }
+
+    async fn reply(&self, parent: i32, text: &str) -> Result<(), Box<dyn Error>> {
+        let page = self.get_page(&format!("reply?id={}", parent)).await?;
+        let hmac = match hidden_input(&page, "hmac") {
+            Some(x) => x,
+            None => return Err(format!("item {} can't be replied to", parent).into())
+        };
+
+        let parent = parent.to_string();
+        let goto = format!("item?id={}", parent);
+        self.post_form("comment", &[("parent", &parent), ("goto", &goto), ("hmac", &hmac), ("text", text)]).await
+    }
+
+    async fn submit(&self, title: &str, url: &str, text: &str) -> Result<(), Box<dyn Error>> {
+        let page = self.get_page("submit").await?;
+        let (fnid, fnop) = match (hidden_input(&page, "fnid"), hidden_input(&page, "fnop")) {
+            (Some(fnid), Some(fnop)) => (fnid, fnop),
+            _ => return Err("no submission form on the submit page".into())
+        };
+
+        self.post_form("r", &[("fnid", &fnid), ("fnop", &fnop), ("title", title), ("url", url), ("text", text)]).await
+    }
+
+    /// Ids of the stories in one of the account's lists on the website,
+    /// following its pages to the end
+    pub async fn story_list(&self, feed: Feed) -> Result<Vec<u32>, Box<dyn Error>> {
+        let list = match feed.site_path() {
+            Some(x) => x,
+            None => return Err(format!("{} isn't a list of the account", feed.title()).into())
+        };
+        let row = Regex::new(r#"<tr class=['"]athing[^'"]*['"] id=['"](\d+)['"]"#).unwrap();
+
+        let mut ids = Vec::new();
+        for page_number in 1..=MAX_LIST_PAGES {
+            let path = format!("{}?id={}&p={}", list, self.username, page_number);
+            let page = self.get_page(&path).await?;
+            ids.extend(
+                row.captures_iter(&page)
+                    .filter_map(|captures| captures[1].parse::<u32>().ok())
+            );
+
+            // The last page has no link to the next one
+            let next = format!("p={}", page_number + 1);
+            if !links(&page).iter().any(|link| link.starts_with(list) && link.contains(&next)) {
+                break;
+            }
+        }
+
+        Ok(ids)
+    }
+}
+
+/// Pages of a list followed before giving up, at 30 stories a page
+const MAX_LIST_PAGES: usize = 50;
+
+fn http_client() -> reqwest::Client {
+    // Redirects are left alone so the cookie set by the login response can be
+    // read, and so that actions don't fetch the page they redirect back to
+    reqwest::Client::builder()
+        .redirect(redirect::Policy::none())
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap()
+}
+
+/// Targets of the links on a page, with `&amp;` decoded
+fn links(page: &str) -> Vec<String> {
+    Regex::new(r#"href=['"]([^'"]*)['"]"#)
+        .unwrap()
+        .captures_iter(page)
+        .map(|captures| captures[1].replace("&amp;", "&"))
+        .collect()
+}
+
+/// Text that a page shows, leaving out markup, scripts and styles
+fn page_text(page: &str) -> String {
+    let document = Html::parse_document(page);
+    let hidden = ["head", "script", "style", "noscript", "template"];
+
+    let mut words = Vec::new();
+    for node in document.tree.root().descendants() {
+        let text = match node.value() {
+            Node::Text(text) => text,
+            _ => continue
+        };
+        let is_hidden = node
+            .ancestors()
+            .any(|ancestor| matches!(ancestor.value(), Node::Element(element) if hidden.contains(&element.name())));
+        if !is_hidden {
+            words.extend(text.split_whitespace());
+        }
+    }
+
+    words.join(" ")
+}
+
+/// Pages show a logout link to logged in accounts
+fn is_logged_in(page: &str) -> bool {
+    links(page).iter().any(|link| link.starts_with("logout"))
+}
+
+/// Value of a hidden form field, such as the token a form has to be posted with
+fn hidden_input(page: &str, name: &str) -> Option<String> {
+    let attribute = Regex::new(r#"(\w+)=['"]([^'"]*)['"]"#).unwrap();
+
+    Regex::new(r"<input[^>]*>")
+        .unwrap()
+        .find_iter(page)
+        .find_map(|input| {
+            let attributes: Vec<(String, String)> = attribute
+                .captures_iter(input.as_str())
+                .map(|captures| (captures[1].to_string(), captures[2].to_string()))
+                .collect();
+            let value = |key: &str| attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+
+            if value("name").as_deref() == Some(name) {
+                value("value")
+            } else {
+                None
+            }
+        })
+}
+
+/// Finds the link for an action on an item, e.g. `vote?id=1&how=up&auth=...`.
+/// Each parameter must have the given value, or be missing when it's `None`
+pub fn find_link(page: &str, action: &str, id: i32, params: &[(&str, Option<&str>)]) -> Option<String> {
+    let id = id.to_string();
+
+    links(page).into_iter().find(|link| {
+        let (path, query) = match link.split_once('?') {
+            Some(x) => x,
+            None => return false
+        };
+        let pairs: Vec<(&str, &str)> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .collect();
+        let value = |key: &str| pairs.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+
+        path == action
+            && value("id") == Some(id.as_str())
+            && params.iter().all(|(key, expected)| value(key) == *expected)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        thread
+    };
+
+    use super::*;
+
+    const ITEM: &str = include_str!("fixtures/item.html");
+    const VOTED: &str = include_str!("fixtures/voted.html");
+    const REPLY: &str = include_str!("fixtures/reply.html");
+    const FAVORITES_1: &str = include_str!("fixtures/favorites-1.html");
+    const FAVORITES_2: &str = include_str!("fixtures/favorites-2.html");
+    const LOGGED_OUT: &str = include_str!("fixtures/logged-out.html");
+    const LOGIN_FAILED: &str = include_str!("fixtures/login-failed.html");
+    const REJECTED: &str = include_str!("fixtures/rejected.html");
+
+    fn page(body: &str) -> String {
+        format!("HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)
+    }
+
+    fn redirect(location: &str, cookie: Option<&str>) -> String {
+        let cookie = cookie.map(|cookie| format!("Set-Cookie: {}\r\n", cookie)).unwrap_or_default();
+        format!("HTTP/1.1 302 Moved\r\nLocation: {}\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n", location, cookie)
+    }
+
+    /// Stands in for the website, answering requests that start with one of
+    /// the routes, e.g. `GET /item?id=1`, and 404 to the rest. Returns its url
+    /// and the requests it received, headers and body included
+    fn serve(routes: Vec<(&'static str, String)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let received = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(x) => x,
+                    Err(_) => return
+                };
+                let request = read_request(&mut stream);
+                let response = routes
+                    .iter()
+                    .find(|(route, _)| request.starts_with(route))
+                    .map(|(_, response)| response.clone())
+                    .unwrap_or_else(|| String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"));
+                received.lock().unwrap().push(request);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn read_request(stream: &mut impl Read) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = stream.read(&mut buffer).unwrap_or(0);
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            let (head, body) = match text.split_once("\r\n\r\n") {
+                Some(x) => x,
+                None if read == 0 => return text,
+                None => continue
+            };
+            let length = head
+                .lines()
+                .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().to_string()))
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or(0);
+            if body.len() >= length || read == 0 {
+                return text;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn login_keeps_the_cookie() {
+        let (url, requests) = serve(vec![("POST /login", redirect("news", Some("user=alice&f00; Path=/; Secure")))]);
+
+        let session = Session::login(&url, "alice", "hunter2").await.unwrap();
+
+        assert_eq!(session.cookie, "alice&f00");
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains("acct=alice&pw=hunter2&goto=news"));
+    }
+
+    #[tokio::test]
+    async fn login_fails_without_a_cookie() {
+        let (url, _) = serve(vec![("POST /login", page(LOGIN_FAILED))]);
+
+        let error = Session::login(&url, "alice", "wrong").await.err().unwrap();
+
+        assert_eq!(error.to_string(), "login failed for alice");
+    }
+
+    #[test]
+    fn find_link_matches_the_action_and_its_params() {
+        let upvote = find_link(ITEM, "vote", 123, &[("how", Some("up"))]);
+        assert_eq!(upvote.as_deref(), Some("vote?id=123&how=up&auth=abc123&goto=item%3Fid%3D123"));
+        assert_eq!(find_link(ITEM, "vote", 123, &[("how", Some("un"))]), None);
+        assert_eq!(find_link(ITEM, "fave", 123, &[("un", None)]).as_deref(), Some("fave?id=123&auth=abc123"));
+        assert_eq!(find_link(ITEM, "fave", 123, &[("un", Some("t"))]), None);
+
+        // Links for the comments on the page don't count for the story
+        let comment = find_link(ITEM, "vote", 456, &[("how", Some("up"))]);
+        assert_eq!(comment.as_deref(), Some("vote?id=456&how=up&auth=def456&goto=item%3Fid%3D123#456"));
+        assert_eq!(find_link(ITEM, "vote", 789, &[("how", Some("up"))]), None);
+
+        assert!(find_link(VOTED, "vote", 123, &[("how", Some("un"))]).is_some());
+        assert!(find_link(VOTED, "fave", 123, &[("un", Some("t"))]).is_some());
+        assert_eq!(find_link(VOTED, "fave", 123, &[("un", None)]), None);
+    }
+
+    #[test]
+    fn hidden_input_reads_form_fields() {
+        assert_eq!(hidden_input(REPLY, "hmac").as_deref(), Some("9f8e7d6c5b4a"));
+        assert_eq!(hidden_input(REPLY, "parent").as_deref(), Some("456"));
+        assert_eq!(hidden_input(REPLY, "goto").as_deref(), Some("item?id=123#456"));
+        assert_eq!(hidden_input(REPLY, "fnid"), None);
+        assert_eq!(hidden_input(ITEM, "hmac"), None);
+    }
+
+    #[test]
+    fn logged_in_pages_have_a_logout_link() {
+        assert!(is_logged_in(ITEM));
+        assert!(is_logged_in(FAVORITES_2));
+        assert!(!is_logged_in(LOGGED_OUT));
+        assert!(!is_logged_in(LOGIN_FAILED));
+    }
+
+    #[tokio::test]
+    async fn story_list_follows_the_pages() {
+        let (url, requests) = serve(vec![
+            ("GET /favorites?id=alice&p=1 ", page(FAVORITES_1)),
+            ("GET /favorites?id=alice&p=2 ", page(FAVORITES_2))
+        ]);
+        let session = Session::new(&url, "alice", "alice&f00");
+
+        let ids = session.story_list(Feed::Favorites).await.unwrap();
+
+        assert_eq!(ids, [101, 102, 103]);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|request| request.contains("cookie: user=alice&f00")));
+    }
+
+    #[tokio::test]
+    async fn story_list_only_covers_account_lists() {
+        let session = Session::new("http://127.0.0.1:9", "alice", "alice&f00");
+
+        assert!(session.story_list(Feed::Top).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn expired_cookie_is_logged_out() {
+        let (url, _) = serve(vec![("GET /item?id=123 ", page(LOGGED_OUT))]);
+        let session = Session::new(&url, "alice", "expired");
+
+        let error = session.perform(&Action::Upvote(123)).await.err().unwrap();
+
+        assert!(error.is::<LoggedOut>());
+    }
+
+    #[tokio::test]
+    async fn forbidden_action_is_logged_out() {
+        let (url, _) = serve(vec![
+            ("GET /item?id=123 ", page(ITEM)),
+            ("GET /vote?id=123", String::from("HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"))
+        ]);
+        let session = Session::new(&url, "alice", "alice&f00");
+
+        let error = session.perform(&Action::Upvote(123)).await.err().unwrap();
+
+        assert!(error.is::<LoggedOut>());
+    }
+
+    #[tokio::test]
+    async fn upvote_follows_the_link_on_the_item_page() {
+        let (url, requests) = serve(vec![
+            ("GET /item?id=123 ", page(ITEM)),
+            ("GET /vote?id=123&how=up&auth=abc123", redirect("item?id=123", None))
+        ]);
+        let session = Session::new(&url, "alice", "alice&f00");
+
+        session.perform(&Action::Upvote(123)).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("GET /vote?id=123&how=up&auth=abc123&goto=item%3Fid%3D123 "));
+    }
+
+    #[tokio::test]
+    async fn reply_posts_with_the_hmac() {
+        let (url, requests) = serve(vec![
+            ("GET /reply?id=456 ", page(REPLY)),
+            ("POST /comment", redirect("item?id=123#456", None))
+        ]);
+        let session = Session::new(&url, "alice", "alice&f00");
+
+        let reply = Action::Reply {
+            parent: 456,
+            text: String::from("Thanks")
+        };
+        session.perform(&reply).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[1].ends_with("parent=456&goto=item%3Fid%3D456&hmac=9f8e7d6c5b4a&text=Thanks"));
+    }
+
+    #[tokio::test]
+    async fn rejected_reply_reports_the_page_text() {
+        let (url, _) = serve(vec![
+            ("GET /reply?id=456 ", page(REPLY)),
+            ("POST /comment", page(REJECTED))
+        ]);
+        let session = Session::new(&url, "alice", "alice&f00");
+
+        let reply = Action::Reply {
+            parent: 456,
+            text: String::from("Thanks")
+        };
+        let error = session.perform(&reply).await.err().unwrap();
+
+        assert_eq!(error.to_string(), "alice | logout You're posting too fast. Please slow down. Thanks, from AT&T & everyone.");
+    }
+
+    #[test]
+    fn page_text_leaves_out_markup_and_scripts() {
+        assert_eq!(page_text("<p>a <b>bold</b>\n move</p><script>if (a && b) {}</script>"), "a bold move");
+        assert_eq!(page_text("plain text"), "plain text");
+    }
+}
@@ -1,8 +1,18 @@
-#[derive(serde::Deserialize, Debug)]
+use regex::Regex;
+
+use crate::hackernews::client::client;
+
+#[derive(serde::Deserialize, Debug, Clone)]
 pub struct Story {
     #[serde(default)]
     pub by: String,
 
+    #[serde(default)]
+    pub deleted: bool,
+
+    #[serde(default)]
+    pub dead: bool,
+
     #[serde(default)]
     pub descendants: i32,
 
@@ -12,9 +22,17 @@ pub struct Story {
     #[serde(default)]
     pub kids: Vec<i32>,
 
+    /// Ids of the options of a poll
+    #[serde(default)]
+    pub parts: Vec<i32>,
+
     #[serde(default)]
     pub score: i32,
 
+    /// Body of Ask HN posts, polls and jobs, as HTML
+    #[serde(default)]
+    pub text: String,
+
     #[serde(default)]
     pub time: u32,
 
@@ -28,36 +46,173 @@ pub struct Story {
     pub url: String
 }
 
-async fn get_story(story_id: u32) -> Story {
-    reqwest::get(format!("https://hacker-news.firebaseio.com/v0/item/{}.json", story_id))
-        .await
-        .unwrap()
-        .json::<Story>()
+impl Story {
+    pub fn is_poll(&self) -> bool {
+        self.r#type == "poll"
+    }
+
+    pub fn is_job(&self) -> bool {
+        self.r#type == "job"
+    }
+
+    /// Company and role of a job posting, parsed from titles such as
+    /// "Acme (YC S21) Is Hiring a Senior Engineer" or "Acme (YC W20) – Designer".
+    /// Titles that don't follow either form, or that name no role, such as
+    /// "Acme (YC S21) Is Hiring", have the whole title as the role
+    pub fn job_posting(&self) -> (Option<String>, String) {
+        let title = self.title.trim();
+
+        let hiring = Regex::new(r"(?i)\s+is\s+hiring\b[\s:,-]*").unwrap();
+        let article = Regex::new(r"(?i)^an?\s+").unwrap();
+        let posting = match hiring.find(title) {
+            Some(found) => {
+                let role = &title[found.end()..];
+                let role = article.find(role).map_or(role, |found| &role[found.end()..]);
+                Some((&title[..found.start()], role))
+            }
+            None => [" – ", " — ", " - ", " | "]
+                .iter()
+                .find_map(|separator| title.split_once(separator))
+        };
+
+        match posting {
+            Some((company, role)) if !role.trim().is_empty() => (Some(company.trim().to_string()), role.trim().to_string()),
+            Some((company, _)) => (Some(company.trim().to_string()), title.to_string()),
+            None => (None, title.to_string())
+        }
+    }
+
+    /// Title to show for the story, with a placeholder standing in for the
+    /// title of deleted stories, and of dead stories unless `show_dead` is set
+    pub fn display_title(&self, show_dead: bool) -> String {
+        if self.deleted {
+            String::from("[deleted]")
+        } else if self.dead && !show_dead {
+            String::from("[dead]")
+        } else if self.dead {
+            format!("[dead] {}", self.title)
+        } else {
+            self.title.clone()
+        }
+    }
+}
+
+pub async fn get_story(story_id: u32) -> Result<Story, Box<dyn std::error::Error>> {
+    client()
+        .get_item::<Story>(story_id as i32)
         .await
-        .unwrap()
 }
 
-pub async fn top_stories(max_stories: usize) -> Result<Vec<Story>, Box<dyn std::error::Error>> {
-    let mut top_stories: Vec<Story> = Vec::new();
+/// Lists of stories, either published by the API or kept with the logged in
+/// account on the website
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Feed {
+    Top,
+    Jobs,
+    Favorites,
+    Upvoted
+}
+
+impl Feed {
+    /// Path of the feed relative to the API url, none for the lists of the
+    /// account, which the API doesn't publish
+    pub fn path(&self) -> Option<&'static str> {
+        match self {
+            Feed::Top => Some("topstories.json"),
+            Feed::Jobs => Some("jobstories.json"),
+            Feed::Favorites | Feed::Upvoted => None
+        }
+    }
+
+    /// Page of the website listing the stories, for the lists of the account
+    pub fn site_path(&self) -> Option<&'static str> {
+        match self {
+            Feed::Favorites => Some("favorites"),
+            Feed::Upvoted => Some("upvoted"),
+            Feed::Top | Feed::Jobs => None
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Feed::Top => "Top Stories",
+            Feed::Jobs => "Jobs",
+            Feed::Favorites => "Favorites",
+            Feed::Upvoted => "Upvoted"
+        }
+    }
+}
+
+/// Ids of every story in a feed, in ranked order
+pub async fn story_ids(feed: Feed) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let path = match feed.path() {
+        Some(x) => x,
+        None => return Err(format!("{} isn't published by the API", feed.title()).into())
+    };
 
-    let resp = reqwest::get("https://hacker-news.firebaseio.com/v0/topstories.json")
-        .await?
-        .json::<Vec<u32>>()
-        .await?;
+    client()
+        .get::<Vec<u32>>(path)
+        .await
+}
 
+/// Fetches the stories with the given ids, keeping their order. Fails if any
+/// of them can't be fetched
+pub async fn get_stories(story_ids: &[u32]) -> Result<Vec<Story>, Box<dyn std::error::Error>> {
     let mut story_futures = Vec::new();
-    for (i, story_id) in resp.iter().enumerate() {
-        if i == max_stories {
-            break;
-        }
+    for story_id in story_ids {
         let story = get_story(*story_id);
         story_futures.push(story)
     }
-    let stories = futures::future::join_all(story_futures).await;
 
-    for story in stories {
-        top_stories.push(story);
+    futures::future::try_join_all(story_futures).await
+}
+
+/// Ids of every story in a feed along with the first `max_stories` of those
+/// stories. The rest can be fetched a page at a time with `get_stories`
+pub async fn feed_stories(feed: Feed, max_stories: usize) -> Result<(Vec<u32>, Vec<Story>), Box<dyn std::error::Error>> {
+    let ids = story_ids(feed).await?;
+    let stories = get_stories(&ids[..max_stories.min(ids.len())]).await?;
+
+    Ok((ids, stories))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posting(title: &str) -> (Option<String>, String) {
+        let story: Story = serde_json::from_value(serde_json::json!({ "title": title, "type": "job" })).unwrap();
+        story.job_posting()
     }
 
-    Ok(top_stories)
+    fn company_and_role(company: &str, role: &str) -> (Option<String>, String) {
+        (Some(company.to_string()), role.to_string())
+    }
+
+    #[test]
+    fn hiring_titles_in_any_case() {
+        assert_eq!(posting("Acme (YC S21) Is Hiring a Senior Engineer"), company_and_role("Acme (YC S21)", "Senior Engineer"));
+        assert_eq!(posting("Acme is Hiring an Engineer"), company_and_role("Acme", "Engineer"));
+        assert_eq!(posting("Acme IS HIRING Designers"), company_and_role("Acme", "Designers"));
+        assert_eq!(posting("Acme (YC W20) is hiring: Rust developers"), company_and_role("Acme (YC W20)", "Rust developers"));
+    }
+
+    #[test]
+    fn hiring_titles_without_a_role_keep_the_title() {
+        assert_eq!(posting("Acme (YC S21) Is Hiring"), company_and_role("Acme (YC S21)", "Acme (YC S21) Is Hiring"));
+        assert_eq!(posting("Acme is hiring "), company_and_role("Acme", "Acme is hiring"));
+    }
+
+    #[test]
+    fn separated_titles() {
+        assert_eq!(posting("Acme (YC W20) – Designer"), company_and_role("Acme (YC W20)", "Designer"));
+        assert_eq!(posting("Acme | Staff Engineer | Remote"), company_and_role("Acme", "Staff Engineer | Remote"));
+    }
+
+    #[test]
+    fn other_titles_are_the_role() {
+        assert_eq!(posting("Work on compilers at Acme"), (None, String::from("Work on compilers at Acme")));
+        // "hiring" has to be a word of its own
+        assert_eq!(posting("Acme is hiringly fast"), (None, String::from("Acme is hiringly fast")));
+    }
 }
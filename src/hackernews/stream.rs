@@ -0,0 +1,226 @@
+use std::error::Error;
+
+use crate::hackernews::client::{client, Client};
+
+/// An event from Firebase's REST streaming protocol. `put` replaces the data
+/// at `path` while `patch` updates some of its children. The data itself is
+/// left out, since the view is refetched rather than patched
+#[derive(Debug, Clone)]
+pub struct StreamEvent {
+    pub event: String,
+    pub path: String
+}
+
+#[derive(serde::Deserialize)]
+struct EventData {
+    #[serde(default)]
+    path: String
+}
+
+/// Splits a `text/event-stream` body into events. Chunks can end anywhere,
+/// including in the middle of a multi byte character, so bytes are buffered
+/// until a complete event has arrived
+#[derive(Debug, Default)]
+pub struct EventParser {
+    buffer: Vec<u8>
+}
+
+impl EventParser {
+    pub fn new() -> EventParser {
+        EventParser::default()
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<StreamEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = find_event_end(&self.buffer) {
+            let raw: Vec<u8> = self.buffer.drain(..end.0 + end.1).collect();
+            let raw = String::from_utf8_lossy(&raw[..end.0]);
+            if let Some(event) = parse_event(&raw) {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+}
+
+/// Position and length of the blank line that ends the first buffered event
+fn find_event_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    for i in 0..buffer.len() {
+        if buffer[i..].starts_with(b"\n\n") {
+            return Some((i, 2));
+        }
+        if buffer[i..].starts_with(b"\r\n\r\n") {
+            return Some((i, 4));
+        }
+    }
+    None
+}
+
+fn parse_event(raw: &str) -> Option<StreamEvent> {
+    let mut event = String::from("message");
+    let mut data_lines = Vec::new();
+
+    for line in raw.lines() {
+        let (field, value) = match line.find(':') {
+            Some(0) => continue,
+            Some(i) => (&line[..i], line[i + 1..].trim_start()),
+            None => (line, "")
+        };
+        match field {
+            "event" => event = value.to_string(),
+            "data" => data_lines.push(value),
+            _ => {}
+        }
+    }
+
+    // Keep-alives and auth events carry `null` rather than a path and data
+    let data: Option<EventData> = serde_json::from_str(&data_lines.join("\n")).ok();
+    let path = data.map(|data| data.path).unwrap_or_default();
+
+    Some(StreamEvent { event, path })
+}
+
+/// Subscribes to a path of the API, e.g. `topstories.json`, calling
+/// `on_event` for every `put` and `patch` until the server closes the stream
+/// or cancels the subscription
+pub async fn subscribe<F: FnMut(StreamEvent)>(path: &str, on_event: F) -> Result<(), Box<dyn Error>> {
+    subscribe_with(client(), path, on_event).await
+}
+
+async fn subscribe_with<F: FnMut(StreamEvent)>(client: &Client, path: &str, mut on_event: F) -> Result<(), Box<dyn Error>> {
+    let mut response = client.stream(path).await?;
+    let mut parser = EventParser::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        for event in parser.feed(&chunk) {
+            match event.event.as_str() {
+                "put" | "patch" => on_event(event),
+                "cancel" | "auth_revoked" => {
+                    return Err(format!("stream of {} was closed: {}", path, event.event).into());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+        time::Duration
+    };
+
+    use super::*;
+    use crate::hackernews::client::Limits;
+
+    #[test]
+    fn feed_waits_for_events_split_across_chunks() {
+        let mut parser = EventParser::new();
+
+        assert!(parser.feed(b"event: put\ndata: {\"path\":\"/\",").is_empty());
+        assert!(parser.feed(b"\"data\":[1,2]}\n").is_empty());
+        let events = parser.feed(b"\nevent: patch\ndata: {\"path\":\"/0\",\"data\":3}\n\n");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "put");
+        assert_eq!(events[0].path, "/");
+        assert_eq!(events[1].event, "patch");
+        assert_eq!(events[1].path, "/0");
+    }
+
+    #[test]
+    fn feed_handles_crlf_line_endings() {
+        let mut parser = EventParser::new();
+        let events = parser.feed(b"event: put\r\ndata: {\"path\":\"/\",\"data\":1}\r\n\r\nevent: keep-alive\r\ndata: null\r\n\r\n");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "put");
+        assert_eq!(events[0].path, "/");
+        assert_eq!(events[1].event, "keep-alive");
+        assert_eq!(events[1].path, "");
+    }
+
+    #[test]
+    fn feed_keeps_characters_split_across_chunks() {
+        let mut parser = EventParser::new();
+        let body = "event: put\ndata: {\"path\":\"/café\",\"data\":1}\n\n".as_bytes();
+        // Splitting in the middle of the two bytes of the é
+        let split = body.iter().position(|byte| *byte == 0xc3).unwrap() + 1;
+
+        assert!(parser.feed(&body[..split]).is_empty());
+        let events = parser.feed(&body[split..]);
+
+        assert_eq!(events[0].path, "/café");
+    }
+
+    /// Serves a single request with an event stream sent in the given chunks,
+    /// closing the connection after the last one
+    fn serve(chunks: Vec<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    return;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n").unwrap();
+            for chunk in chunks {
+                stream.write_all(chunk.as_bytes()).unwrap();
+                stream.flush().unwrap();
+                thread::sleep(Duration::from_millis(20));
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn subscribe_passes_on_changes_until_closed() {
+        let url = serve(vec![
+            "event: put\ndata: {\"path\":\"/\",\"data\":[1,2]}\n\n",
+            "event: keep-alive\ndata: null\n\n",
+            "event: patch\ndata: {\"path\":\"/\",",
+            "\"data\":{\"0\":3}}\n\n"
+        ]);
+        let client = Client::new(&url, false, Limits::default()).unwrap();
+
+        let mut events = Vec::new();
+        subscribe_with(&client, "topstories.json", |event| events.push(event)).await.unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "put");
+        assert_eq!(events[1].event, "patch");
+        assert_eq!(events[1].path, "/");
+    }
+
+    #[tokio::test]
+    async fn subscribe_fails_when_cancelled() {
+        let url = serve(vec![
+            "event: put\ndata: {\"path\":\"/\",\"data\":1}\n\n",
+            "event: cancel\ndata: null\n\n"
+        ]);
+        let client = Client::new(&url, false, Limits::default()).unwrap();
+
+        let mut events = Vec::new();
+        let result = subscribe_with(&client, "item/1.json", |event| events.push(event)).await;
+
+        assert_eq!(events.len(), 1);
+        assert!(result.unwrap_err().to_string().contains("cancel"));
+    }
+}
@@ -0,0 +1,29 @@
+use crate::hackernews::client::client;
+
+/// Items that changed recently, as reported by `/v0/updates`. The profiles
+/// that changed are reported too, but nothing shown depends on them
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct Updates {
+    #[serde(default)]
+    pub items: Vec<i32>
+}
+
+pub async fn get_updates() -> Result<Updates, Box<dyn std::error::Error>> {
+    client().get::<Updates>("updates.json").await
+}
+
+/// Id of the newest item on the site
+pub async fn max_item() -> Result<i32, Box<dyn std::error::Error>> {
+    client().get::<i32>("maxitem.json").await
+}
+
+/// Polls for changes and drops the changed items from the cache so that the
+/// next fetch picks up their new contents
+pub async fn poll() -> Result<(Updates, i32), Box<dyn std::error::Error>> {
+    let updates = get_updates().await?;
+    let max_item = max_item().await?;
+
+    client().invalidate(&updates.items);
+
+    Ok((updates, max_item))
+}
@@ -0,0 +1,29 @@
+use crate::hackernews::client::client;
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct User {
+    #[serde(default)]
+    pub id: String,
+
+    #[serde(default)]
+    pub about: String,
+
+    #[serde(default)]
+    pub created: i64,
+
+    #[serde(default)]
+    pub karma: i32,
+
+    #[serde(default)]
+    pub submitted: Vec<i32>
+}
+
+pub async fn get_user(user_id: &str) -> Result<User, Box<dyn std::error::Error>> {
+    let mut user = client()
+        .get::<User>(&format!("user/{}.json", user_id))
+        .await?;
+
+    user.about = crate::hackernews::comments::clean_text(&user.about);
+
+    Ok(user)
+}
@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use reqwest::Url;
+use scraper::{node::Node, ElementRef, Html, Selector};
+
+use super::{Article, Block};
+
+/// Elements that never hold the article, along with everything inside them.
+/// Headers are kept since inside the article they hold its title and byline
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "object",
+    "nav", "footer", "aside", "form", "button", "input", "select", "textarea", "img"
+];
+
+/// Elements that start a new block of text
+const BLOCK_TAGS: &[&str] = &[
+    "p", "div", "section", "article", "main", "ul", "ol", "dl", "dt", "dd", "table",
+    "tr", "td", "th", "figure", "figcaption", "hr", "details", "summary", "center", "header"
+];
+
+/// Paragraphs shorter than this don't count towards the content of an element
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+/// Characters of paragraph text that an `article` or `main` element needs for
+/// it to be taken as the content without scoring the page
+const MIN_CONTENT_LENGTH: usize = 200;
+
+/// Reads the article out of a page, leaving behind the navigation, sidebars,
+/// comments and other clutter around it. Relative links are resolved
+/// against `url`
+pub fn extract(html: &str, url: &str) -> Article {
+    let document = Html::parse_document(html);
+    let title = title(&document);
+
+    let mut walker = Walker {
+        base: Url::parse(url).ok(),
+        unlikely: Regex::new(r"(?i)comment|share|social|sidebar|related|promo|advert|sponsor|cookie|newsletter|subscribe|signup|popup|modal|breadcrumb|menu|masthead|footer|nav").unwrap(),
+        likely: Regex::new(r"(?i)article|body|content|main|post|entry|story|text").unwrap(),
+        blocks: Vec::new(),
+        links: Vec::new(),
+        text: String::new(),
+        contexts: Vec::new()
+    };
+    let root = content(&document);
+    walker.walk(root);
+    walker.flush();
+
+    // The title usually starts the article too
+    let mut blocks = walker.blocks;
+    if let Some(Block::Heading(_, heading)) = blocks.first() {
+        if *heading == title {
+            blocks.remove(0);
+        }
+    }
+
+    Article {
+        title,
+        url: url.to_string(),
+        blocks,
+        links: walker.links
+    }
+}
+
+/// Title of the page, preferring the one meant for sharing, which leaves out
+/// the name of the site
+fn title(document: &Html) -> String {
+    let first = |selector: &str, text: bool| {
+        let selector = Selector::parse(selector).unwrap();
+        document.select(&selector).find_map(|element| {
+            let value = if text {
+                element.text().collect::<String>()
+            } else {
+                element.value().attr("content")?.to_string()
+            };
+            let value = collapse_whitespace(&value);
+            if value.is_empty() {
+                None
+            } else {
+                Some(value)
+            }
+        })
+    };
+
+    first(r#"meta[property="og:title"]"#, false)
+        .or_else(|| first("title", true))
+        .or_else(|| first("h1", true))
+        .unwrap_or_default()
+}
+
+/// The element holding the article. Pages that mark it up as an `article` or
+/// `main` element get that, while the rest are scored by their paragraphs
+/// like readability does, with each paragraph counting towards its parent and
+/// less towards its grandparent
+fn content(document: &Html) -> ElementRef<'_> {
+    let paragraphs = Selector::parse("p, pre").unwrap();
+    let paragraph_length = |element: &ElementRef| -> usize {
+        element
+            .select(&paragraphs)
+            .map(|paragraph| paragraph.text().map(str::len).sum::<usize>())
+            .sum()
+    };
+
+    for selector in &["article", "main", r#"[role="main"]"#] {
+        let selector = Selector::parse(selector).unwrap();
+        let best = document
+            .select(&selector)
+            .map(|element| (paragraph_length(&element), element))
+            .max_by_key(|(length, _)| *length);
+        if let Some((length, element)) = best {
+            if length >= MIN_CONTENT_LENGTH {
+                return element;
+            }
+        }
+    }
+
+    let mut scores = HashMap::new();
+    for paragraph in document.select(&paragraphs) {
+        let text: String = paragraph.text().collect();
+        let length = text.trim().len();
+        if length < MIN_PARAGRAPH_LENGTH {
+            continue;
+        }
+
+        let score = 1.0 + text.matches(',').count() as f64 + (length / 100).min(3) as f64;
+        let parent = paragraph.parent().and_then(ElementRef::wrap);
+        if let Some(parent) = parent {
+            *scores.entry(parent.id()).or_insert(0.0) += score;
+            if let Some(grandparent) = parent.parent().and_then(ElementRef::wrap) {
+                *scores.entry(grandparent.id()).or_insert(0.0) += score / 2.0;
+            }
+        }
+    }
+
+    let best = scores
+        .into_iter()
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .and_then(|(id, _)| document.tree.get(id))
+        .and_then(ElementRef::wrap);
+
+    let body = Selector::parse("body").unwrap();
+    best.or_else(|| document.select(&body).next())
+        .unwrap_or_else(|| document.root_element())
+}
+
+/// Kind of block that text is gathered into
+#[derive(Clone, Copy)]
+enum Context {
+    Heading(u8),
+    ListItem,
+    Quote
+}
+
+/// Goes through the content in document order, gathering inline text until
+/// an element that starts a new block
+struct Walker {
+    base: Option<Url>,
+    unlikely: Regex,
+    likely: Regex,
+    blocks: Vec<Block>,
+    links: Vec<String>,
+
+    /// Text of the block being gathered
+    text: String,
+    contexts: Vec<Context>
+}
+
+impl Walker {
+    fn walk(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.push_text(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, element: ElementRef) {
+        let name = element.value().name();
+        if SKIPPED_TAGS.contains(&name) || self.is_clutter(&element) {
+            return;
+        }
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                self.block(element, Some(Context::Heading(level)));
+            }
+            "li" => self.block(element, Some(Context::ListItem)),
+            "blockquote" => self.block(element, Some(Context::Quote)),
+            "pre" => {
+                self.flush();
+                let code: String = element.text().collect();
+                let code = code.trim_end().trim_start_matches('\n');
+                if !code.is_empty() {
+                    self.blocks.push(Block::Code(code.to_string()));
+                }
+            }
+            "br" => self.text.push('\n'),
+            "a" => {
+                self.walk(element);
+                self.push_link(element.value().attr("href"));
+            }
+            _ if BLOCK_TAGS.contains(&name) => self.block(element, None),
+            _ => self.walk(element)
+        }
+    }
+
+    /// Gathers an element into blocks of its own
+    fn block(&mut self, element: ElementRef, context: Option<Context>) {
+        self.flush();
+        if let Some(context) = context {
+            self.contexts.push(context);
+        }
+        self.walk(element);
+        self.flush();
+        if context.is_some() {
+            self.contexts.pop();
+        }
+    }
+
+    /// Whether an element is something like a share bar or comment section,
+    /// going by its class and id
+    fn is_clutter(&self, element: &ElementRef) -> bool {
+        let value = element.value();
+        let names = format!("{} {}", value.classes().collect::<Vec<&str>>().join(" "), value.id().unwrap_or(""));
+
+        self.unlikely.is_match(&names) && !self.likely.is_match(&names)
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let text = collapse_whitespace_keeping_edges(text);
+        if text.is_empty() || (text == " " && (self.text.is_empty() || self.text.ends_with(char::is_whitespace))) {
+            return;
+        }
+        if text.starts_with(' ') && self.text.ends_with(char::is_whitespace) {
+            self.text.push_str(&text[1..]);
+        } else {
+            self.text.push_str(&text);
+        }
+    }
+
+    /// Numbers a link after its text, e.g. `the paper [3]`, so it can be
+    /// looked up in the list of links at the end of the article
+    fn push_link(&mut self, href: Option<&str>) {
+        let href = match href {
+            Some(x) if !x.starts_with('#') && !x.starts_with("javascript:") && !x.starts_with("mailto:") => x,
+            _ => return
+        };
+        let url = match &self.base {
+            Some(base) => match base.join(href) {
+                Ok(x) => x.to_string(),
+                Err(_) => return
+            },
+            None => href.to_string()
+        };
+
+        let number = match self.links.iter().position(|link| *link == url) {
+            Some(index) => index + 1,
+            None => {
+                self.links.push(url);
+                self.links.len()
+            }
+        };
+        let spacer = if self.text.ends_with(char::is_whitespace) { "" } else { " " };
+        self.text.push_str(&format!("{}[{}]", spacer, number));
+    }
+
+    /// Ends the block being gathered, dropping it when it has no text
+    fn flush(&mut self) {
+        let text = self
+            .text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        self.text.clear();
+
+        // A block holding nothing but link numbers is left over from a list
+        // of links, such as tags
+        if text.is_empty() || text.split_whitespace().all(|word| word.starts_with('[') && word.ends_with(']')) {
+            return;
+        }
+
+        let block = match self.contexts.last() {
+            Some(Context::Heading(level)) => Block::Heading(*level, text),
+            Some(Context::ListItem) => Block::ListItem(text),
+            Some(Context::Quote) => Block::Quote(text),
+            None => Block::Paragraph(text)
+        };
+        self.blocks.push(block);
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Collapses runs of whitespace into a single space, keeping one at either
+/// end so that words in neighbouring elements stay apart
+fn collapse_whitespace_keeping_edges(text: &str) -> String {
+    let mut collapsed = String::new();
+    if text.starts_with(char::is_whitespace) {
+        collapsed.push(' ');
+    }
+    collapsed.push_str(&collapse_whitespace(text));
+    if text.ends_with(char::is_whitespace) && !collapsed.ends_with(' ') {
+        collapsed.push(' ');
+    }
+
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOG: &str = include_str!("fixtures/blog.html");
+    const ARTICLE: &str = include_str!("fixtures/article.html");
+    const MAIN: &str = include_str!("fixtures/main.html");
+
+    const URL: &str = "https://blog.example.com/posts/why-rust";
+
+    fn paragraph(text: &str) -> Block {
+        Block::Paragraph(text.to_string())
+    }
+
+    #[test]
+    fn article_element_is_the_content() {
+        let article = extract(ARTICLE, URL);
+
+        assert_eq!(article.title, "News");
+        assert_eq!(article.blocks, [
+            Block::Heading(1, String::from("Big news")),
+            paragraph("By a reporter"),
+            paragraph("This is an article element with enough text to be taken as the content straight away without any scoring."),
+            paragraph("It has a second paragraph too, so that the text is long enough to pass the minimum content length check here.")
+        ]);
+    }
+
+    #[test]
+    fn main_element_is_the_content_when_articles_are_short() {
+        let article = extract(MAIN, URL);
+
+        assert_eq!(article.title, "Docs - Getting started");
+        assert_eq!(article.blocks[0], Block::Heading(1, String::from("Getting started")));
+        assert_eq!(article.blocks.len(), 4);
+        assert!(!article.blocks.contains(&paragraph("A teaser for another page, too short to be the content.")));
+    }
+
+    #[test]
+    fn pages_without_markup_are_scored() {
+        let document = Html::parse_document(BLOG);
+
+        assert_eq!(content(&document).value().id(), Some("content"));
+    }
+
+    #[test]
+    fn clutter_is_left_out() {
+        let article = extract(BLOG, URL);
+        let text: Vec<String> = article.blocks.iter().map(|block| format!("{:?}", block)).collect();
+        let text = text.join("\n");
+
+        for clutter in &["Home", "Related", "sidebar", "Tweet", "A comment", "Copyright", "var x"] {
+            assert!(!text.contains(clutter), "{} wasn't left out", clutter);
+        }
+    }
+
+    #[test]
+    fn blocks_keep_their_kind() {
+        let article = extract(BLOG, URL);
+
+        // The title is left out of the blocks, being shown already
+        assert_eq!(article.title, "Why Rust");
+        assert_eq!(article.blocks, [
+            paragraph("Rust is a language [1] empowering everyone to build reliable and efficient software, with memory safety, and no GC."),
+            paragraph("Second paragraph with line breaks,\nand a break, and a link [2]."),
+            Block::Heading(2, String::from("Features")),
+            Block::ListItem(String::from("Fast, really fast")),
+            Block::ListItem(String::from("Safe again [1]")),
+            Block::Quote(String::from("Quoted wisdom, with commas, many of them.")),
+            Block::Code(String::from("fn main() {\n    println!(\"hi\");\n}")),
+            paragraph("日本語のテキストも含まれています、とても長い文章です。"),
+            paragraph("See the notes or write.")
+        ]);
+    }
+
+    #[test]
+    fn links_are_numbered_once_each() {
+        let article = extract(BLOG, URL);
+
+        // Fragments and mail links aren't numbered, and a paragraph holding
+        // nothing but a link's number is dropped
+        assert_eq!(article.links, [
+            "https://blog.example.com/lang",
+            "https://example.com/x",
+            "https://blog.example.com/feed.xml"
+        ]);
+    }
+
+    #[test]
+    fn links_are_kept_as_they_are_without_a_base() {
+        let article = extract(BLOG, "not a url");
+
+        assert_eq!(article.links[0], "/lang");
+    }
+
+    #[test]
+    fn pre_blocks_keep_their_whitespace() {
+        let article = extract(MAIN, URL);
+
+        // Leading blank lines and trailing whitespace are trimmed, and the
+        // indentation is not
+        assert_eq!(article.blocks[3], Block::Code(String::from("  $ tool --init\n  wrote ~/.config/tool/config.json")));
+    }
+}
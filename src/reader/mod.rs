@@ -0,0 +1,196 @@
+use std::{error::Error, fs, path::{Path, PathBuf}, time::Duration};
+
+use reqwest::{header, Url};
+
+pub mod extract;
+
+/// The main content of a linked page, read out of its html
+#[derive(Debug, Clone)]
+pub struct Article {
+    pub title: String,
+    pub url: String,
+    pub blocks: Vec<Block>,
+
+    /// Targets of the links in the text, which are numbered from 1 by their
+    /// position in this list
+    pub links: Vec<String>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    /// Heading and its level, 1 to 6
+    Heading(u8, String),
+    Paragraph(String),
+    Quote(String),
+    ListItem(String),
+    /// Preformatted text, with its whitespace kept
+    Code(String)
+}
+
+/// Largest page that is read, so that a link to a huge or endless response
+/// can't use up the memory
+const MAX_PAGE_SIZE: usize = 5 * 1024 * 1024;
+
+/// Fetches a page over http and reads the article out of it
+pub async fn fetch(url: &str) -> Result<Article, Box<dyn Error>> {
+    match Url::parse(url) {
+        Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => {}
+        _ => return Err(format!("{} isn't a web url", url).into())
+    }
+
+    let mut response = reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent(concat!("hackernews-tui/", env!("CARGO_PKG_VERSION")))
+        .build()?
+        .get(url)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{} returned {}", url, status).into());
+    }
+
+    // Links to pdfs, images and the like have no article to read
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("text/html")
+        .to_string();
+    if !content_type.contains("html") {
+        return Err(format!("{} isn't a web page ({})", url, content_type).into());
+    }
+
+    // Checking the size as the body comes in too, since it may not be given
+    // up front
+    let too_large = || format!("{} is larger than {} MB", url, MAX_PAGE_SIZE / 1024 / 1024);
+    if response.content_length().is_some_and(|length| length > MAX_PAGE_SIZE as u64) {
+        return Err(too_large().into());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_PAGE_SIZE {
+            return Err(too_large().into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    // Redirects are followed, so links are resolved against where the page
+    // ended up
+    let final_url = response.url().to_string();
+    let html = String::from_utf8_lossy(&body);
+
+    Ok(extract::extract(&html, &final_url))
+}
+
+/// Reads the article out of a saved page, given by path or `file://` url,
+/// which is handy for checking the extraction. Only meant for paths given on
+/// the command line, links in stories are always fetched with `fetch`
+pub fn fetch_file(path: &str) -> Result<Article, Box<dyn Error>> {
+    let path = match local_path(path) {
+        Some(x) => x,
+        None => return Err(format!("{} isn't a file", path).into())
+    };
+
+    let html = fs::read_to_string(&path)?;
+    let url = match fs::canonicalize(&path).ok().and_then(|path| Url::from_file_path(path).ok()) {
+        Some(x) => x.to_string(),
+        None => path.display().to_string()
+    };
+
+    Ok(extract::extract(&html, &url))
+}
+
+/// Path that the argument of the `read` command names, when it's a file
+/// rather than a web url
+pub fn local_path(url: &str) -> Option<PathBuf> {
+    if url.starts_with("file://") {
+        return Url::parse(url).ok()?.to_file_path().ok();
+    }
+    if !url.contains("://") && Path::new(url).is_file() {
+        return Some(PathBuf::from(url));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread
+    };
+
+    use super::*;
+
+    const ARTICLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/reader/fixtures/article.html");
+
+    /// Answers every request with the given response
+    fn serve(response: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(x) => x,
+                    Err(_) => return
+                };
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read])
+                    }
+                }
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        url
+    }
+
+    fn page(headers: &str, body: &str) -> String {
+        format!("HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n{}Connection: close\r\n\r\n{}", headers, body)
+    }
+
+    #[tokio::test]
+    async fn fetch_reads_web_pages() {
+        let body = "<html><head><title>Post</title></head><body><p>Some text</p></body></html>";
+        let url = serve(page(&format!("Content-Length: {}\r\n", body.len()), body));
+
+        let article = fetch(&url).await.unwrap();
+        assert_eq!(article.title, "Post");
+        assert!(article.url.starts_with(&url));
+    }
+
+    #[tokio::test]
+    async fn fetch_only_reads_web_urls() {
+        let file_url = Url::from_file_path(ARTICLE).unwrap().to_string();
+
+        assert!(fetch(&file_url).await.is_err());
+        assert!(fetch(ARTICLE).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_refuses_large_pages() {
+        let url = serve(page(&format!("Content-Length: {}\r\n", MAX_PAGE_SIZE + 1), ""));
+        assert!(fetch(&url).await.unwrap_err().to_string().contains("larger than"));
+
+        // Without a length up front, the body is cut off as it comes in
+        let url = serve(page("", &"a".repeat(MAX_PAGE_SIZE + 1)));
+        assert!(fetch(&url).await.unwrap_err().to_string().contains("larger than"));
+    }
+
+    #[test]
+    fn fetch_file_reads_paths_and_file_urls() {
+        let file_url = Url::from_file_path(ARTICLE).unwrap().to_string();
+
+        assert_eq!(fetch_file(ARTICLE).unwrap().url, file_url);
+        assert_eq!(fetch_file(&file_url).unwrap().url, file_url);
+        assert!(fetch_file("https://example.com/post").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::hackernews::{comments::Comment, stories::Story};
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BookmarkKind {
+    Story,
+    Comment
+}
+
+/// A saved story or comment. A snapshot of the item is kept so the saved list
+/// can be rendered without hitting the API, while the id allows refetching the
+/// full item once it has dropped off the front page
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bookmark {
+    pub id: i32,
+
+    pub kind: BookmarkKind,
+
+    #[serde(default)]
+    pub title: String,
+
+    #[serde(default)]
+    pub by: String,
+
+    #[serde(default)]
+    pub url: String,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub note: String,
+
+    /// Unix timestamp of when the item was saved
+    #[serde(default)]
+    pub saved: i64
+}
+
+impl Bookmark {
+    pub fn from_story(story: &Story) -> Bookmark {
+        Bookmark {
+            id: story.id,
+            kind: BookmarkKind::Story,
+            title: story.title.clone(),
+            by: story.by.clone(),
+            url: story.url.clone(),
+            tags: Vec::new(),
+            note: String::new(),
+            saved: Local::now().timestamp()
+        }
+    }
+
+    pub fn from_comment(comment: &Comment) -> Bookmark {
+        // Comments have no title, so the beginning of the text stands in
+        let title: String = comment.text.chars().take(80).collect();
+
+        Bookmark {
+            id: comment.id,
+            kind: BookmarkKind::Comment,
+            title,
+            by: comment.by.clone(),
+            url: format!("https://news.ycombinator.com/item?id={}", comment.id),
+            tags: Vec::new(),
+            note: String::new(),
+            saved: Local::now().timestamp()
+        }
+    }
+
+    pub fn saved_date(&self) -> String {
+        Local.timestamp(self.saved, 0)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
+
+pub fn load() -> Result<Vec<Bookmark>, Box<dyn std::error::Error>> {
+    crate::storage::load(BOOKMARKS_FILE)
+}
+
+pub fn save(bookmarks: &[Bookmark]) -> Result<(), Box<dyn std::error::Error>> {
+    crate::storage::save(BOOKMARKS_FILE, &bookmarks)
+}
+
+/// Adds the bookmark, or removes it if an item with the same id was already
+/// saved. Returns whether the item is saved afterwards
+pub fn toggle(bookmarks: &mut Vec<Bookmark>, bookmark: Bookmark) -> bool {
+    match bookmarks.iter().position(|b| b.id == bookmark.id) {
+        Some(i) => {
+            bookmarks.remove(i);
+            false
+        }
+        None => {
+            bookmarks.push(bookmark);
+            true
+        }
+    }
+}
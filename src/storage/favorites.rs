@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use crate::hackernews::stories::Feed;
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+/// Copy of the story lists kept with the account on the website, so they can
+/// be read without logging in until they're synced again
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Favorites {
+    #[serde(default)]
+    pub favorites: Vec<u32>,
+
+    #[serde(default)]
+    pub upvoted: Vec<u32>,
+
+    /// Unix timestamp of the last sync, 0 when the lists were never synced
+    #[serde(default)]
+    pub synced: i64
+}
+
+impl Favorites {
+    /// Ids of the stories in one of the lists, most recently added first
+    pub fn ids(&self, feed: Feed) -> Vec<u32> {
+        match feed {
+            Feed::Favorites => self.favorites.clone(),
+            Feed::Upvoted => self.upvoted.clone(),
+            Feed::Top | Feed::Jobs => Vec::new()
+        }
+    }
+}
+
+pub fn load() -> Result<Favorites, Box<dyn std::error::Error>> {
+    super::load(FAVORITES_FILE)
+}
+
+pub fn save(favorites: &Favorites) -> Result<(), Box<dyn std::error::Error>> {
+    super::save(FAVORITES_FILE, favorites)
+}
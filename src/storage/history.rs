@@ -0,0 +1,123 @@
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::hackernews::stories::Story;
+
+const HISTORY_FILE: &str = "history.json";
+
+/// A story that was opened, along with a snapshot of how it looked at the time
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub id: i32,
+
+    #[serde(default)]
+    pub title: String,
+
+    #[serde(default)]
+    pub url: String,
+
+    #[serde(default)]
+    pub score: i32,
+
+    /// Unix timestamp of when the story was opened
+    #[serde(default)]
+    pub opened: i64
+}
+
+impl HistoryEntry {
+    pub fn from_story(story: &Story) -> HistoryEntry {
+        HistoryEntry {
+            id: story.id,
+            title: story.title.clone(),
+            url: story.url.clone(),
+            score: story.score,
+            opened: Local::now().timestamp()
+        }
+    }
+
+    pub fn opened_date(&self) -> String {
+        Local.timestamp(self.opened, 0)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
+
+/// Narrows down the history by title and by the date range a story was opened
+/// in, both ends of the range being inclusive
+#[derive(Debug, Default, Clone)]
+pub struct HistoryFilter {
+    pub title: Option<String>,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>
+}
+
+impl HistoryFilter {
+    /// Parses a search query such as `rust since:2021-04-01 until:2021-04-07`.
+    /// Words that aren't a date bound are matched against the title
+    pub fn parse(query: &str) -> Result<HistoryFilter, chrono::ParseError> {
+        let mut filter = HistoryFilter::default();
+        let mut title_words = Vec::new();
+
+        for word in query.split_whitespace() {
+            if let Some(date) = word.strip_prefix("since:") {
+                filter.since = Some(NaiveDate::parse_from_str(date, "%Y-%m-%d")?);
+            } else if let Some(date) = word.strip_prefix("until:") {
+                filter.until = Some(NaiveDate::parse_from_str(date, "%Y-%m-%d")?);
+            } else {
+                title_words.push(word);
+            }
+        }
+
+        if !title_words.is_empty() {
+            filter.title = Some(title_words.join(" "));
+        }
+
+        Ok(filter)
+    }
+
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        if let Some(title) = &self.title {
+            if !entry.title.to_lowercase().contains(&title.to_lowercase()) {
+                return false;
+            }
+        }
+
+        let opened = Local.timestamp(entry.opened, 0).date().naive_local();
+        if let Some(since) = self.since {
+            if opened < since {
+                return false;
+            }
+        }
+        if let Some(until) = self.until {
+            if opened > until {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+pub fn load() -> Result<Vec<HistoryEntry>, Box<dyn std::error::Error>> {
+    crate::storage::load(HISTORY_FILE)
+}
+
+pub fn save(history: &[HistoryEntry]) -> Result<(), Box<dyn std::error::Error>> {
+    crate::storage::save(HISTORY_FILE, &history)
+}
+
+/// Appends a story to the history and writes it to disk
+pub fn record(history: &mut Vec<HistoryEntry>, story: &Story) -> Result<(), Box<dyn std::error::Error>> {
+    history.push(HistoryEntry::from_story(story));
+    save(history)
+}
+
+/// Entries matching the filter, most recently opened first
+pub fn search(history: &[HistoryEntry], filter: &HistoryFilter) -> Vec<HistoryEntry> {
+    history
+        .iter()
+        .rev()
+        .filter(|entry| filter.matches(entry))
+        .cloned()
+        .collect()
+}
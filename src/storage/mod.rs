@@ -0,0 +1,162 @@
+pub mod bookmarks;
+pub mod favorites;
+pub mod history;
+pub mod session;
+
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf}
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Directory where locally stored data lives, following the XDG base
+/// directory spec (`$XDG_DATA_HOME/hackernews-tui`, usually
+/// `~/.local/share/hackernews-tui`)
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("hackernews-tui")
+}
+
+/// Reads a JSON file out of the data directory, falling back to the default
+/// value when the file hasn't been written yet
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> Result<T, Box<dyn Error>> {
+    load_from(&data_dir().join(file_name))
+}
+
+/// Reads a JSON file, falling back to the default value when it doesn't
+/// exist. A file that can't be parsed is moved aside to `<name>.bad` so that
+/// a damaged store doesn't keep the program from starting
+fn load_from<T: DeserializeOwned + Default>(path: &Path) -> Result<T, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+
+    let contents = fs::read_to_string(path)?;
+    match serde_json::from_str(&contents) {
+        Ok(x) => Ok(x),
+        Err(error) => {
+            let backup = with_suffix(path, "bad");
+            log::error!("{} can't be read ({}), starting over and keeping it as {}", path.display(), error, backup.display());
+            fs::rename(path, &backup)?;
+            Ok(T::default())
+        }
+    }
+}
+
+pub fn save<T: Serialize>(file_name: &str, value: &T) -> Result<(), Box<dyn Error>> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+
+    write_atomically(&dir.join(file_name), &serde_json::to_string_pretty(value)?, 0o666)
+}
+
+/// Like `save`, but for files holding secrets such as a session cookie, which
+/// only the owner may read
+pub fn save_private<T: Serialize>(file_name: &str, value: &T) -> Result<(), Box<dyn Error>> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+
+    write_atomically(&dir.join(file_name), &serde_json::to_string_pretty(value)?, 0o600)
+}
+
+/// Writes a file by way of a temporary one that is renamed over it, so that
+/// the file is either left as it was or completely written, even when the
+/// program is stopped halfway through
+fn write_atomically(path: &Path, contents: &str, mode: u32) -> Result<(), Box<dyn Error>> {
+    let temporary = with_suffix(path, "tmp");
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(mode)
+        .open(&temporary)?;
+    // The mode only applies to new files, so a temporary file left behind
+    // with other permissions is brought in line too
+    if mode == 0o600 {
+        file.set_permissions(fs::Permissions::from_mode(mode))?;
+    }
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// The path with an extra extension, e.g. `history.json.tmp`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own for each test, removed when it ends
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("hackernews-tui-test-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn writes_replace_the_file() {
+        let dir = TempDir::new("write");
+        let path = dir.0.join("history.json");
+
+        write_atomically(&path, "[1]", 0o666).unwrap();
+        write_atomically(&path, "[1, 2]", 0o666).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[1, 2]");
+        assert!(!with_suffix(&path, "tmp").exists());
+        assert_eq!(load_from::<Vec<i32>>(&path).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn private_writes_are_only_readable_by_the_owner() {
+        let dir = TempDir::new("private");
+        let path = dir.0.join("session.json");
+        fs::write(with_suffix(&path, "tmp"), "left behind").unwrap();
+        fs::set_permissions(with_suffix(&path, "tmp"), fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_atomically(&path, "{}", 0o600).unwrap();
+
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn damaged_files_are_moved_aside() {
+        let dir = TempDir::new("damaged");
+        let path = dir.0.join("bookmarks.json");
+        fs::write(&path, "[{\"id\": 1, \"ki").unwrap();
+
+        assert!(load_from::<Vec<i32>>(&path).unwrap().is_empty());
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(with_suffix(&path, "bad")).unwrap(), "[{\"id\": 1, \"ki");
+    }
+
+    #[test]
+    fn missing_files_are_empty() {
+        let dir = TempDir::new("missing");
+
+        assert!(load_from::<Vec<i32>>(&dir.0.join("history.json")).unwrap().is_empty());
+    }
+}
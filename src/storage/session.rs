@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+const SESSION_FILE: &str = "session.json";
+
+/// Cookie of the last login, so that the password isn't sent on every start
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SavedSession {
+    #[serde(default)]
+    pub username: String,
+
+    #[serde(default)]
+    pub cookie: String
+}
+
+pub fn load() -> Result<Option<SavedSession>, Box<dyn std::error::Error>> {
+    let session: SavedSession = super::load(SESSION_FILE)?;
+    if session.cookie.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(session))
+}
+
+pub fn save(session: &SavedSession) -> Result<(), Box<dyn std::error::Error>> {
+    super::save_private(SESSION_FILE, session)
+}
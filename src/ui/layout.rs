@@ -0,0 +1,209 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns taken by the borders of a list and its highlight symbol
+const LIST_CHROME: usize = 5;
+
+/// Columns taken by one level of a thread gutter
+const GUTTER_WIDTH: usize = 2;
+
+/// Columns that the text of a comment keeps before indentation stops growing,
+/// when the terminal is wide enough for it
+const MIN_TEXT_WIDTH: usize = 20;
+
+/// Where the lines of a comment go in a list of the given width
+#[derive(Debug, PartialEq)]
+pub struct CommentLayout {
+    /// Levels of gutter drawn to the left of the comment
+    pub indent: usize,
+
+    /// Columns left for the text
+    pub text_width: usize
+}
+
+/// Lays out a comment at `depth` in a list that is `width` columns wide. The
+/// indentation is capped at `max_indent`, and further whenever it would leave
+/// the text narrower than `MIN_TEXT_WIDTH`. The text is at least a column wide
+/// however narrow the list is
+pub fn comment_layout(width: usize, depth: usize, max_indent: Option<usize>) -> CommentLayout {
+    // The comment's own gutter is always drawn
+    let available = width.saturating_sub(LIST_CHROME + GUTTER_WIDTH);
+    let room = available.saturating_sub(MIN_TEXT_WIDTH) / GUTTER_WIDTH;
+
+    let indent = depth.min(max_indent.unwrap_or(depth)).min(room);
+
+    CommentLayout {
+        indent,
+        text_width: available.saturating_sub(indent * GUTTER_WIDTH).max(1)
+    }
+}
+
+/// Columns that text takes up on the terminal, where wide characters such as
+/// CJK and most emoji take two
+pub fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Wraps text at word boundaries into lines at most `width` columns wide.
+/// Line breaks in the text are kept, as is the indentation at the start of a
+/// line unless it leaves no room for the first word. Words longer than a line
+/// are broken up. The only lines that can be wider are those holding a single
+/// character that is itself wider than `width`
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let text = text.replace('\t', "    ");
+
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+
+        for chunk in chunks(paragraph) {
+            let word = chunk.trim_end_matches(' ');
+            if line_width + display_width(word) <= width {
+                line.push_str(chunk);
+                line_width += display_width(chunk);
+                continue;
+            }
+
+            // Indentation followed by a word too long for the rest of the line
+            // stays in front of the word's first piece
+            let first_width = word.chars().next().and_then(|character| character.width()).unwrap_or(0);
+            let indent = if line.trim_end().is_empty() && line_width + first_width <= width {
+                std::mem::take(&mut line)
+            } else {
+                String::new()
+            };
+            if !line.trim_end().is_empty() {
+                lines.push(line.trim_end().to_string());
+            }
+
+            // Words too long for a line of their own are broken up, with the
+            // last piece starting the next line
+            let mut pieces = break_word(word, width - display_width(&indent), width);
+            pieces[0].insert_str(0, &indent);
+            let last = pieces.pop().unwrap_or_default();
+            lines.extend(pieces);
+
+            line = format!("{}{}", last, &chunk[word.len()..]);
+            line_width = display_width(&line);
+        }
+
+        lines.push(line.trim_end().to_string());
+    }
+
+    lines
+}
+
+/// Splits a paragraph into words along with the spaces following them, so
+/// spaces at the start of a line, like the indentation of code, are kept
+fn chunks(paragraph: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+
+    for (index, character) in paragraph.char_indices() {
+        if previous == Some(' ') && character != ' ' && index > start {
+            chunks.push(&paragraph[start..index]);
+            start = index;
+        }
+        previous = Some(character);
+    }
+    if start < paragraph.len() {
+        chunks.push(&paragraph[start..]);
+    }
+
+    chunks
+}
+
+/// Breaks a word into pieces at most `width` columns wide, or `first_width`
+/// for the first one, each holding at least one character
+fn break_word(word: &str, first_width: usize, width: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    let mut piece_width = 0;
+
+    for character in word.chars() {
+        let character_width = character.width().unwrap_or(0);
+        let limit = if pieces.is_empty() { first_width } else { width };
+        if piece_width + character_width > limit && !piece.is_empty() {
+            pieces.push(std::mem::take(&mut piece));
+            piece_width = 0;
+        }
+        piece.push(character);
+        piece_width += character_width;
+    }
+    pieces.push(piece);
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(indent: usize, text_width: usize) -> CommentLayout {
+        CommentLayout { indent, text_width }
+    }
+
+    #[test]
+    fn comment_layout_on_narrow_lists() {
+        for width in &[0, 1, 5] {
+            assert_eq!(comment_layout(*width, 0, None), layout(0, 1));
+            assert_eq!(comment_layout(*width, 40, None), layout(0, 1));
+            assert_eq!(comment_layout(*width, 40, Some(3)), layout(0, 1));
+        }
+
+        // Too narrow for any indentation to leave the minimum text width
+        assert_eq!(comment_layout(20, 40, None), layout(0, 13));
+        assert_eq!(comment_layout(27, 40, None), layout(0, 20));
+        assert_eq!(comment_layout(29, 40, None), layout(1, 20));
+    }
+
+    #[test]
+    fn comment_layout_on_wide_lists() {
+        assert_eq!(comment_layout(47, 3, None), layout(3, 34));
+        assert_eq!(comment_layout(47, 50, None), layout(10, 20));
+        assert_eq!(comment_layout(47, 50, Some(2)), layout(2, 36));
+        assert_eq!(comment_layout(200, 100, None), layout(86, 21));
+        assert_eq!(comment_layout(200, 100, Some(8)), layout(8, 177));
+    }
+
+    #[test]
+    fn wrap_breaks_at_words() {
+        assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(wrap("first\n\nthird", 10), ["first", "", "third"]);
+        assert_eq!(wrap("a verylongword", 4), ["a", "very", "long", "word"]);
+        assert_eq!(wrap("word", 0), ["w", "o", "r", "d"]);
+    }
+
+    #[test]
+    fn wrap_keeps_indentation() {
+        assert_eq!(wrap("  indented code\nline two", 8), ["  indent", "ed code", "line two"]);
+        assert_eq!(wrap("    let x = 1;\n\tx", 20), ["    let x = 1;", "    x"]);
+        assert_eq!(wrap("  ab cd", 8), ["  ab cd"]);
+
+        // Indentation as wide as the line leaves no room for the text
+        assert_eq!(wrap("          word", 8), ["word"]);
+    }
+
+    #[test]
+    fn wrap_measures_wide_characters() {
+        assert_eq!(wrap("日本語のテキスト", 5), ["日本", "語の", "テキ", "スト"]);
+        assert_eq!(wrap("hello 世界 world", 8), ["hello", "世界", "world"]);
+        assert_eq!(wrap("🦀🦀🦀 ok", 4), ["🦀🦀", "🦀", "ok"]);
+        assert_eq!(wrap("  世界", 3), ["世", "界"]);
+
+        // A character wider than the line gets a line of its own
+        assert_eq!(wrap("世界", 1), ["世", "界"]);
+    }
+
+    #[test]
+    fn wrapped_lines_fit() {
+        let text = "Ünïcödé 日本語 🦀 text, with  double spaces and a_very_long_identifier_here\n  and indented 世界 lines";
+        for width in 2..40 {
+            for line in wrap(text, width) {
+                assert!(display_width(&line) <= width, "{:?} is wider than {}", line, width);
+            }
+        }
+    }
+}
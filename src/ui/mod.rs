@@ -0,0 +1,899 @@
+use std::time::Instant;
+
+use chrono::{Local, TimeZone};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+mod layout;
+
+use crate::{
+    app::{draft::Draft, App, CommentsView, DraftView, HistoryView, JobView, RankChange, ReaderView, StoriesView, UserView, View},
+    hackernews::{comments::{clean_text, Comment}, polls::PollOption, stories::Story},
+    reader::Block as ArticleBlock,
+    storage::bookmarks::{Bookmark, BookmarkKind},
+    util::StatefulList
+};
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let mut size = f.size();
+    app.width = size.width;
+
+    // Reserving the last line for the outcome of the last action
+    if let Some(status) = &app.status {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(size);
+        size = chunks[0];
+
+        let status = Paragraph::new(format!(" {}", status))
+            .style(
+                Style::default()
+                .fg(Color::LightYellow)
+            );
+        f.render_widget(status, chunks[1]);
+    }
+
+    // Reserving the bottom of the screen for the prompt when it's open
+    if let Some((_, prompt)) = &app.prompt {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(size);
+        size = chunks[0];
+
+        let input = Paragraph::new(prompt.input.as_ref())
+            .block(
+                Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", prompt.label))
+            );
+        f.render_widget(input, chunks[1]);
+    }
+
+    let show_dead = app.config.show_dead;
+
+    let split = app.shows_preview();
+    let thread_style = ThreadStyle {
+        friends: app.config.friends.clone(),
+        palette: app.config.theme.thread_colors.clone(),
+        max_indent: if app.compact_threads {
+            Some(app.config.max_indent.max(0) as usize)
+        } else {
+            None
+        }
+    };
+    match app.current() {
+        View::Stories(view) | View::Search(view) => {
+            if split {
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                    .split(size);
+                draw_stories(f, view, chunks[0], show_dead);
+                draw_preview(f, view, chunks[1], show_dead);
+            } else {
+                draw_stories(f, view, size, show_dead);
+            }
+        }
+        View::Comments(view) | View::Item(view) => draw_comments(f, view, size, show_dead, &thread_style),
+        View::User(view) => draw_user(f, view, size),
+        View::Job(view) => draw_job(f, view, size),
+        View::Reader(view) => draw_reader(f, view, size),
+        View::Draft(view) => draw_draft(f, view, size),
+        View::Saved(list) => draw_saved(f, list, size),
+        View::History(view) => draw_history(f, view, size)
+    }
+}
+
+/// Bordered list with the highlight style shared by every view
+fn styled_list<'a>(items: Vec<ListItem<'a>>, title: String) -> List<'a> {
+    List::new(items)
+        .block(
+            Block::default()
+            .borders(Borders::ALL)
+            .title(
+                Span::styled(title,
+                    Style::default()
+                    .add_modifier(Modifier::BOLD),
+                )
+            )
+        )
+        .highlight_style(
+            Style::default()
+            .fg(Color::LightYellow)
+            .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ")
+}
+
+fn draw_stories<B: Backend>(f: &mut Frame<B>, view: &mut StoriesView, size: Rect, show_dead: bool) {
+    // Preparing a vector of list items
+    let mut items: Vec<ListItem> = view
+        .list
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, data)| {
+            // Movement since the previous fetch of the feed
+            let movement = match view.rank_change(i, data) {
+                RankChange::New => Span::styled("new ", Style::default().fg(Color::LightBlue)),
+                RankChange::Up(n) => Span::styled(format!("▲{} ", n), Style::default().fg(Color::LightGreen)),
+                RankChange::Down(n) => Span::styled(format!("▼{} ", n), Style::default().fg(Color::LightRed)),
+                RankChange::Same => Span::raw("")
+            };
+
+            // Job postings have no score or comments, so they're shown by
+            // company and role instead
+            if data.is_job() && !data.deleted && (show_dead || !data.dead) {
+                return ListItem::new(job_item(i, movement, data));
+            }
+
+            let title = Spans::from(vec![
+                Span::styled(
+                    format!("{}) ", i + 1),
+                    Style::default()
+                ),
+                movement,
+                Span::styled(
+                    data.display_title(show_dead),
+                    Style::default()
+                )
+            ]);
+
+            let score_delta = match view.score_delta(data) {
+                0 => String::new(),
+                delta => format!(" ({:+})", delta)
+            };
+            let url = Spans::from(vec![
+                Span::styled(
+                    format!("Score: {}{} | Comments: {} | URL: {}", data.score, score_delta, data.descendants, data.url),
+                    Style::default()
+                )
+            ]);
+            ListItem::new(vec![
+                title,
+                url,
+                Spans::from("")
+            ])
+        }).
+    collect();
+
+    // Like the website, a row at the bottom shows that the feed continues.
+    // The next page is loaded as the selection gets close to it
+    if view.has_more() {
+        let more = if view.loading_more { "Loading more..." } else { "More" };
+        items.push(ListItem::new(vec![
+            Spans::from(vec![
+                Span::styled(
+                    more,
+                    Style::default()
+                    .fg(Color::DarkGray)
+                )
+            ])
+        ]));
+    }
+
+    // Rendering list data
+    let items_list = styled_list(items, format!(" {} | {} ", view.title, updated_ago(view.updated)));
+    f.render_stateful_widget(items_list, size, &mut view.list.state);
+}
+
+/// Lines of a comment shown in the preview of a story
+const PREVIEW_COMMENT_LINES: usize = 4;
+
+/// Header and top comments of the selected story, next to the list of stories
+fn draw_preview<B: Backend>(f: &mut Frame<B>, view: &StoriesView, size: Rect, show_dead: bool) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Preview ");
+
+    let story = match view.list.selected() {
+        Some(x) => x,
+        None => {
+            f.render_widget(block, size);
+            return;
+        }
+    };
+
+    let width = size.width.saturating_sub(2) as usize;
+    let mut lines = story_header(story, &[], width, show_dead);
+    lines.push(Spans::from(""));
+
+    let dim = Style::default().fg(Color::DarkGray);
+    match view.preview.comments.get(&story.id) {
+        _ if story.kids.is_empty() => lines.push(Spans::from(Span::styled("No comments", dim))),
+        None => lines.push(Spans::from(Span::styled("Loading comments...", dim))),
+        Some(comments) => {
+            for comment in comments {
+                lines.push(Spans::from(Span::styled(
+                    comment.by.clone(),
+                    Style::default()
+                    .add_modifier(Modifier::BOLD)
+                )));
+
+                // Long comments are cut short, the thread has the rest
+                let text = layout::wrap(&comment.display_text(show_dead), width);
+                let cut = text.len() > PREVIEW_COMMENT_LINES;
+                for line in text.into_iter().take(PREVIEW_COMMENT_LINES) {
+                    lines.push(Spans::from(line));
+                }
+                if cut {
+                    lines.push(Spans::from(Span::styled("...", dim)));
+                }
+                lines.push(Spans::from(""));
+            }
+        }
+    }
+
+    f.render_widget(Paragraph::new(lines).block(block), size);
+}
+
+/// Rows of a job posting in a list of stories
+fn job_item<'a>(rank: usize, movement: Span<'a>, job: &Story) -> Vec<Spans<'a>> {
+    let (company, role) = job.job_posting();
+
+    let mut title = vec![
+        Span::styled(
+            format!("{}) ", rank + 1),
+            Style::default()
+        ),
+        movement,
+        Span::styled(
+            "[job] ",
+            Style::default()
+            .fg(Color::LightMagenta)
+        )
+    ];
+    if let Some(company) = company {
+        title.push(Span::styled(
+            company,
+            Style::default()
+            .add_modifier(Modifier::BOLD)
+        ));
+        if !role.is_empty() {
+            title.push(Span::raw(format!(" | {}", role)));
+        }
+    } else {
+        title.push(Span::raw(role));
+    }
+
+    let posted = Local.timestamp(job.time as i64, 0).format("%Y-%m-%d");
+    let details = if job.url.is_empty() {
+        format!("Posted: {}", posted)
+    } else {
+        format!("Posted: {} | URL: {}", posted, job.url)
+    };
+
+    vec![
+        Spans::from(title),
+        Spans::from(details),
+        Spans::from("")
+    ]
+}
+
+/// How long ago a view was last fetched, for showing in its title
+fn updated_ago(updated: Instant) -> String {
+    let seconds = updated.elapsed().as_secs();
+    if seconds < 60 {
+        format!("updated {}s ago", seconds)
+    } else {
+        format!("updated {}m ago", seconds / 60)
+    }
+}
+
+/// Lines of story text shown in the header above its comments
+const HEADER_TEXT_LINES: usize = 4;
+
+/// Summary of a story shown above its comments, including the options and
+/// their scores when the story is a poll
+fn story_header(story: &Story, poll_options: &[PollOption], width: usize, show_dead: bool) -> Vec<Spans<'static>> {
+    let mut header = vec![
+        Spans::from(vec![
+            Span::styled(
+                story.display_title(show_dead),
+                Style::default()
+                .add_modifier(Modifier::BOLD)
+            )
+        ]),
+        Spans::from(format!("Score: {} | By: {} | Comments: {}", story.score, story.by, story.descendants))
+    ];
+
+    if !story.url.is_empty() {
+        header.push(Spans::from(story.url.clone()));
+    }
+
+    // Ask HN posts and polls have a body, of which only the start is shown
+    if !story.text.is_empty() {
+        for line in layout::wrap(&clean_text(&story.text), width).into_iter().take(HEADER_TEXT_LINES) {
+            header.push(Spans::from(line));
+        }
+    }
+
+    if !poll_options.is_empty() {
+        header.push(Spans::from(""));
+
+        // Bars are scaled so that the leading option fills the width
+        let max_score = poll_options.iter().map(|option| option.score).max().unwrap_or(0).max(1);
+        let bar_width = width.saturating_sub(8);
+        for option in poll_options {
+            let bar_length = option.score.max(0) as usize * bar_width / max_score as usize;
+            header.push(Spans::from(option.text.clone()));
+            header.push(Spans::from(vec![
+                Span::styled(
+                    "█".repeat(bar_length),
+                    Style::default()
+                    .fg(Color::LightYellow)
+                ),
+                Span::raw(format!(" {}", option.score))
+            ]));
+        }
+    }
+
+    header
+}
+
+/// How comment threads are drawn, taken from the config
+struct ThreadStyle {
+    friends: Vec<String>,
+
+    /// Colors of the gutters, by depth
+    palette: Vec<Color>,
+
+    /// Levels of indentation shown before it stops growing, in compact mode
+    max_indent: Option<usize>
+}
+
+impl ThreadStyle {
+    fn color(&self, depth: usize) -> Style {
+        Style::default().fg(self.palette[depth % self.palette.len()])
+    }
+}
+
+fn draw_comments<B: Backend>(f: &mut Frame<B>, view: &mut CommentsView, size: Rect, show_dead: bool, style: &ThreadStyle) {
+    let mut size = size;
+
+    // Story header above the comments, taking up at most half of the screen
+    if let Some(story) = &view.story {
+        let header = story_header(story, &view.poll_options, size.width.saturating_sub(4) as usize, show_dead);
+        let height = (header.len() as u16 + 2).min(size.height / 2);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(height), Constraint::Min(0)].as_ref())
+            .split(size);
+        size = chunks[1];
+
+        let header = Paragraph::new(header)
+            .block(
+                Block::default()
+                .borders(Borders::ALL)
+            );
+        f.render_widget(header, chunks[0]);
+    }
+
+    // Status bar below the comments showing where the selection is in the thread
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(size);
+    size = chunks[0];
+    let status = Paragraph::new(breadcrumb(&view.list, chunks[1].width as usize))
+        .style(
+            Style::default()
+            .fg(Color::DarkGray)
+        );
+    f.render_widget(status, chunks[1]);
+
+    let op = view.op().to_string();
+    let lines = thread_lines(&view.list.items);
+
+    // Preparing a vector of list items
+    let items: Vec<ListItem> = view
+        .list
+        .items
+        .iter()
+        .enumerate()
+        .map(|(index, comment)| {
+            let mut list_item: Vec<Spans> = vec![];
+
+            // Deep comments are drawn at a capped indentation, in compact
+            // mode or when the terminal is too narrow for their depth, with
+            // their actual depth shown next to the author. The layout follows
+            // the size of the frame, so the text is wrapped again on resize
+            let depth = comment.depth.max(0) as usize;
+            let layout = layout::comment_layout(size.width as usize, depth, style.max_indent);
+            let indent = layout.indent;
+            let gutter = Gutter { style, lines: &lines[index], depth, indent };
+
+            let text = layout::wrap(&comment.display_text(show_dead), layout.text_width);
+
+            // Dimming the placeholders of deleted and dead comments
+            let text_style = if comment.deleted || (comment.dead && !show_dead) {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+
+            // Pushing the string splits into the display vector, with the
+            // connector to the parent's gutter on the first line
+            for (line, s) in text.into_iter().enumerate() {
+                let mut item = gutter.spans(line == 0);
+                item.push(Span::styled(s, text_style));
+                list_item.push(Spans::from(item));
+            }
+
+            // Name of user who published a comment, marked when it's the
+            // author of the story or someone in the friends list
+            let mut user = gutter.spans(false);
+            user.push(Span::raw("By: "));
+            if !op.is_empty() && comment.by == op {
+                user.push(Span::styled(
+                    format!("{} [OP]", comment.by),
+                    Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+                ));
+            } else if style.friends.contains(&comment.by) {
+                user.push(Span::styled(
+                    format!("{} [friend]", comment.by),
+                    Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+                ));
+            } else {
+                user.push(Span::raw(comment.by.clone()));
+            }
+            if indent < depth {
+                user.push(Span::styled(
+                    format!(" [depth {}]", depth),
+                    Style::default()
+                    .fg(Color::DarkGray)
+                ));
+            }
+
+            // Final pushes for display vector. Deleted comments have no author
+            if !comment.by.is_empty() {
+                list_item.push(Spans::from(user));
+            }
+
+            // Placeholder for replies that are still being loaded, or that
+            // will be loaded once asked for
+            if !comment.replies_loaded && !comment.kids.is_empty() {
+                let reply_count = comment.kids.len();
+                let replies = if reply_count == 1 { "reply" } else { "replies" };
+                let placeholder = if comment.replies_deferred {
+                    format!("  [load {} more {}]", reply_count, replies)
+                } else {
+                    format!("  loading {} {}...", reply_count, replies)
+                };
+                let mut item = gutter.columns(false);
+                item.push(Span::styled(
+                    placeholder,
+                    Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::ITALIC)
+                ));
+                list_item.push(Spans::from(item));
+            }
+
+            // The gap before the next comment keeps the gutters going, down
+            // to the replies of this comment when they come next
+            let mut gap = gutter.columns(false);
+            let has_replies = view.list.items.get(index + 1).is_some_and(|next| next.depth > comment.depth);
+            if has_replies {
+                gap.push(Span::styled("│", style.color(depth)));
+            }
+            list_item.push(Spans::from(gap));
+
+            ListItem::new(list_item)
+        })
+    .collect();
+
+    // Rendering list data
+    let items_list = styled_list(items, format!(" {} | {} | {} ", view.title, view.order.label(), updated_ago(view.updated)));
+    f.render_stateful_widget(items_list, size, &mut view.list.state);
+}
+
+/// For each comment, whether the comment leading down to it at each depth
+/// below the top level has another reply to the same parent further down,
+/// which keeps the gutter line of that depth going past the comment
+fn thread_lines(comments: &[Comment]) -> Vec<Vec<bool>> {
+    // Going backwards, a comment has a later sibling when one at the same
+    // depth was seen since the last comment above its depth
+    let mut has_sibling = vec![false; comments.len()];
+    let mut seen: Vec<bool> = Vec::new();
+    for (index, comment) in comments.iter().enumerate().rev() {
+        let depth = comment.depth.max(0) as usize;
+        seen.resize(depth + 1, false);
+        has_sibling[index] = seen[depth];
+        seen[depth] = true;
+    }
+
+    let mut path: Vec<bool> = Vec::new();
+    comments
+        .iter()
+        .zip(has_sibling)
+        .map(|(comment, has_sibling)| {
+            let depth = comment.depth.max(0) as usize;
+            path.resize(depth.saturating_sub(1), false);
+            if depth > 0 {
+                path.push(has_sibling);
+            }
+            path.clone()
+        })
+        .collect()
+}
+
+/// Thread gutter drawn to the left of the lines of a comment
+struct Gutter<'a> {
+    style: &'a ThreadStyle,
+
+    /// Whether the gutter line of each depth goes on past the comment
+    lines: &'a [bool],
+    depth: usize,
+
+    /// Columns drawn, which is less than the depth when it's capped
+    indent: usize
+}
+
+impl<'a> Gutter<'a> {
+    /// The columns of the parents' gutters, ending in a connector to the
+    /// comment on its first line
+    fn columns(&self, first_line: bool) -> Vec<Span<'static>> {
+        (0..self.indent)
+            .map(|column| {
+                let continues = self.lines.get(column).copied().unwrap_or(false);
+                let symbol = if first_line && column + 1 == self.depth {
+                    if continues { "├─" } else { "└─" }
+                } else if continues {
+                    "│ "
+                } else {
+                    "  "
+                };
+                Span::styled(symbol, self.style.color(column))
+            })
+            .collect()
+    }
+
+    /// The columns followed by the comment's own gutter
+    fn spans(&self, first_line: bool) -> Vec<Span<'static>> {
+        let mut spans = self.columns(first_line);
+        spans.push(Span::styled("│ ", self.style.color(self.depth)));
+        spans
+    }
+}
+
+/// Authors of the comments leading down to the selected one, cut off at the
+/// start when they don't fit in `width`
+fn breadcrumb(list: &StatefulList<Comment>, width: usize) -> String {
+    let selected = match list.selected() {
+        Some(x) => x,
+        None => return String::new()
+    };
+
+    let author = |comment: &Comment| {
+        if comment.by.is_empty() {
+            String::from("[deleted]")
+        } else {
+            comment.by.clone()
+        }
+    };
+    let mut chain: Vec<String> = list.ancestors().into_iter().map(author).collect();
+    chain.push(author(selected));
+    let text = format!(" {}", chain.join(" › "));
+
+    let length = text.chars().count();
+    if length <= width {
+        return text;
+    }
+    let kept: String = text.chars().skip(length - width + 2).collect();
+    format!(" …{}", kept)
+}
+
+fn draw_user<B: Backend>(f: &mut Frame<B>, view: &mut UserView, size: Rect) {
+    let user = &view.user;
+    let created = Local.timestamp(user.created, 0).format("%Y-%m-%d");
+
+    let mut text = vec![
+        Spans::from(format!("Created: {}", created)),
+        Spans::from(format!("Karma: {}", user.karma)),
+        Spans::from(format!("Submissions: {}", user.submitted.len())),
+        Spans::from("")
+    ];
+    for line in user.about.lines() {
+        text.push(Spans::from(line.to_string()));
+    }
+
+    let profile = Paragraph::new(text)
+        .block(
+            Block::default()
+            .borders(Borders::ALL)
+            .title(
+                Span::styled(format!(" User: {} ", user.id),
+                    Style::default()
+                    .add_modifier(Modifier::BOLD),
+                )
+            )
+        )
+        .wrap(Wrap { trim: false })
+        .scroll((view.scroll, 0));
+    f.render_widget(profile, size);
+}
+
+fn draw_job<B: Backend>(f: &mut Frame<B>, view: &mut JobView, size: Rect) {
+    let job = &view.story;
+    let (company, role) = job.job_posting();
+    let posted = Local.timestamp(job.time as i64, 0).format("%Y-%m-%d");
+
+    let mut text = vec![
+        Spans::from(vec![
+            Span::styled(
+                role,
+                Style::default()
+                .add_modifier(Modifier::BOLD)
+            )
+        ]),
+        Spans::from(format!("Posted: {}", posted))
+    ];
+    if !job.url.is_empty() {
+        text.push(Spans::from(format!("URL: {}", job.url)));
+    }
+
+    // Postings that link elsewhere usually have no description
+    text.push(Spans::from(""));
+    if job.text.is_empty() {
+        text.push(Spans::from(vec![
+            Span::styled(
+                "No description, the posting is at its URL",
+                Style::default()
+                .fg(Color::DarkGray)
+            )
+        ]));
+    } else {
+        for line in clean_text(&job.text).lines() {
+            text.push(Spans::from(line.to_string()));
+        }
+    }
+
+    let title = match company {
+        Some(company) => format!(" Job: {} ", company),
+        None => String::from(" Job ")
+    };
+    let description = Paragraph::new(text)
+        .block(
+            Block::default()
+            .borders(Borders::ALL)
+            .title(
+                Span::styled(title,
+                    Style::default()
+                    .add_modifier(Modifier::BOLD),
+                )
+            )
+        )
+        .wrap(Wrap { trim: false })
+        .scroll((view.scroll, 0));
+    f.render_widget(description, size);
+}
+
+/// Article read out of a linked page, with its links numbered in the text
+/// and listed at the end
+fn draw_reader<B: Backend>(f: &mut Frame<B>, view: &mut ReaderView, size: Rect) {
+    let article = &view.article;
+    let width = size.width.saturating_sub(2) as usize;
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let mut text: Vec<Spans> = Vec::new();
+    for line in layout::wrap(&article.title, width) {
+        text.push(Spans::from(Span::styled(line, Style::default().add_modifier(Modifier::BOLD))));
+    }
+    text.push(Spans::from(Span::styled(article.url.clone(), dim)));
+    text.push(Spans::from(""));
+
+    if article.blocks.is_empty() {
+        text.push(Spans::from(Span::styled("No article found on the page, press o to open it in the browser", dim)));
+    }
+
+    for (index, block) in article.blocks.iter().enumerate() {
+        match block {
+            ArticleBlock::Heading(level, heading) => {
+                let style = if *level <= 2 {
+                    Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+                } else {
+                    Style::default().add_modifier(Modifier::BOLD)
+                };
+                for line in layout::wrap(heading, width) {
+                    text.push(Spans::from(Span::styled(line, style)));
+                }
+            }
+            ArticleBlock::Paragraph(paragraph) => {
+                for line in layout::wrap(paragraph, width) {
+                    text.push(Spans::from(line));
+                }
+            }
+            ArticleBlock::Quote(quote) => {
+                for line in layout::wrap(quote, width.saturating_sub(2)) {
+                    text.push(Spans::from(vec![
+                        Span::styled("│ ", dim),
+                        Span::styled(line, Style::default().add_modifier(Modifier::ITALIC))
+                    ]));
+                }
+            }
+            ArticleBlock::ListItem(item) => {
+                // Hanging indent, so the bullet stands out
+                for (number, line) in layout::wrap(item, width.saturating_sub(2)).into_iter().enumerate() {
+                    let bullet = if number == 0 { "• " } else { "  " };
+                    text.push(Spans::from(format!("{}{}", bullet, line)));
+                }
+            }
+            ArticleBlock::Code(code) => {
+                // Code keeps its lines as they are, running off the edge
+                // rather than being wrapped
+                for line in code.lines() {
+                    text.push(Spans::from(Span::styled(format!("  {}", line), Style::default().fg(Color::LightYellow))));
+                }
+            }
+        }
+
+        // Items of the same list stay together
+        let next_is_item = matches!(article.blocks.get(index + 1), Some(ArticleBlock::ListItem(_)));
+        if !(next_is_item && matches!(block, ArticleBlock::ListItem(_))) {
+            text.push(Spans::from(""));
+        }
+    }
+
+    if !article.links.is_empty() {
+        text.push(Spans::from(Span::styled("Links", Style::default().add_modifier(Modifier::BOLD))));
+        for (number, link) in article.links.iter().enumerate() {
+            for line in layout::wrap(&format!("[{}] {}", number + 1, link), width) {
+                text.push(Spans::from(Span::styled(line, dim)));
+            }
+        }
+    }
+
+    // Keeping the last screenful in view when scrolled past the end
+    let page = size.height.saturating_sub(2);
+    let max_scroll = (text.len() as u16).saturating_sub(page);
+    view.page = page;
+    view.scroll = view.scroll.min(max_scroll);
+
+    let host = reqwest::Url::parse(&article.url)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_else(|| String::from("file"));
+    let article = Paragraph::new(text)
+        .block(
+            Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Reader | {} ", host))
+        )
+        .scroll((view.scroll, 0));
+    f.render_widget(article, size);
+}
+
+fn draw_draft<B: Backend>(f: &mut Frame<B>, view: &mut DraftView, size: Rect) {
+    let (title, text) = match &view.draft {
+        Draft::Reply { to, text, .. } => (format!(" Reply to {} ", to), text.clone()),
+        Draft::Submission { title, url, text } => {
+            let mut preview = vec![format!("Title: {}", title)];
+            if !url.is_empty() {
+                preview.push(format!("URL: {}", url));
+            }
+            if !text.is_empty() {
+                preview.push(String::new());
+                preview.push(text.clone());
+            }
+            (String::from(" Submission "), preview.join("\n"))
+        }
+    };
+
+    let lines: Vec<Spans> = text.lines().map(|line| Spans::from(line.to_string())).collect();
+    let preview = Paragraph::new(lines)
+        .block(
+            Block::default()
+            .borders(Borders::ALL)
+            .title(
+                Span::styled(format!("{}| enter: post | e: edit | q: back ", title),
+                    Style::default()
+                    .add_modifier(Modifier::BOLD),
+                )
+            )
+        )
+        .wrap(Wrap { trim: false })
+        .scroll((view.scroll, 0));
+    f.render_widget(preview, size);
+}
+
+fn draw_saved<B: Backend>(f: &mut Frame<B>, list: &mut StatefulList<Bookmark>, size: Rect) {
+    // Preparing a vector of list items
+    let items: Vec<ListItem> = list
+        .items
+        .iter()
+        .map(|bookmark| {
+            let kind = match bookmark.kind {
+                BookmarkKind::Story => "Story",
+                BookmarkKind::Comment => "Comment"
+            };
+            let title = Spans::from(vec![
+                Span::styled(
+                    format!("[{}] {}", kind, bookmark.title),
+                    Style::default()
+                )
+            ]);
+            let details = Spans::from(vec![
+                Span::styled(
+                    format!("By: {} | Saved: {} | Tags: {}", bookmark.by, bookmark.saved_date(), bookmark.tags.join(", ")),
+                    Style::default()
+                )
+            ]);
+
+            let mut list_item = vec![title, details];
+            if !bookmark.note.is_empty() {
+                list_item.push(Spans::from(vec![
+                    Span::styled(
+                        format!("Note: {}", bookmark.note),
+                        Style::default()
+                        .add_modifier(Modifier::ITALIC)
+                    )
+                ]));
+            }
+            list_item.push(Spans::from(""));
+
+            ListItem::new(list_item)
+        })
+    .collect();
+
+    // Rendering list data
+    let items_list = styled_list(items, String::from(" Saved "));
+    f.render_stateful_widget(items_list, size, &mut list.state);
+}
+
+fn draw_history<B: Backend>(f: &mut Frame<B>, view: &mut HistoryView, size: Rect) {
+    // Preparing a vector of list items
+    let items: Vec<ListItem> = view
+        .list
+        .items
+        .iter()
+        .map(|entry| {
+            let title = Spans::from(vec![
+                Span::styled(
+                    entry.title.clone(),
+                    Style::default()
+                )
+            ]);
+            let details = Spans::from(vec![
+                Span::styled(
+                    format!("Opened: {} | Score: {} | URL: {}", entry.opened_date(), entry.score, entry.url),
+                    Style::default()
+                )
+            ]);
+            ListItem::new(vec![
+                title,
+                details,
+                Spans::from("")
+            ])
+        })
+    .collect();
+
+    // Showing the active search in the title
+    let filter = &view.filter;
+    let mut title = String::from(" History ");
+    if let Some(query) = &filter.title {
+        title.push_str(&format!("| \"{}\" ", query));
+    }
+    if let Some(since) = filter.since {
+        title.push_str(&format!("| since {} ", since));
+    }
+    if let Some(until) = filter.until {
+        title.push_str(&format!("| until {} ", until));
+    }
+
+    // Rendering list data
+    let items_list = styled_list(items, title);
+    f.render_stateful_widget(items_list, size, &mut view.list.state);
+}
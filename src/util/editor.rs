@@ -0,0 +1,89 @@
+use std::{
+    env,
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::PathBuf,
+    process::Command
+};
+
+use rand::Rng;
+
+/// Opens text in the user's editor, `$VISUAL` or `$EDITOR` falling back to
+/// `vi`, and returns the text as it was saved once the editor exits
+pub fn edit(text: &str) -> Result<String, Box<dyn Error>> {
+    let (path, mut file) = create_draft()?;
+    let written = file.write_all(text.as_bytes());
+    drop(file);
+    if let Err(error) = written {
+        let _ = fs::remove_file(&path);
+        return Err(error.into());
+    }
+
+    // The editor may come with arguments, e.g. `code --wait`
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    let status = Command::new(program).args(words).arg(&path).status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    if !status?.success() {
+        return Err(format!("{} exited with an error", program).into());
+    }
+
+    Ok(edited?)
+}
+
+/// Creates a file for the draft in the temp directory that only the user can
+/// read. The name is random and the file has to be new, so that a file or
+/// symlink planted there by someone else is never written through
+fn create_draft() -> Result<(PathBuf, File), Box<dyn Error>> {
+    let mut attempts = 0;
+    loop {
+        let suffix: u32 = rand::thread_rng().gen();
+        let path = env::temp_dir().join(format!("hackernews-tui-{}-{:08x}.txt", std::process::id(), suffix));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path);
+
+        match file {
+            Ok(file) => return Ok((path, file)),
+            Err(error) if error.kind() == ErrorKind::AlreadyExists && attempts < 10 => attempts += 1,
+            Err(error) => return Err(error.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    #[test]
+    fn draft_is_new_and_private() {
+        let (first, _) = create_draft().unwrap();
+        let (second, _) = create_draft().unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fs::metadata(&first).unwrap().permissions().mode() & 0o777, 0o600);
+
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn edit_returns_the_saved_text() {
+        // An editor that saves the draft as it is
+        env::set_var("VISUAL", "true");
+
+        assert_eq!(edit("a reply\n").unwrap(), "a reply\n");
+    }
+}
@@ -1,4 +1,5 @@
-use std::io;
+use std::io::Read;
+use std::os::unix::io::AsRawFd;
 use std::sync::mpsc;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -7,20 +8,25 @@ use std::sync::{
 use std::thread;
 use std::time::Duration;
 
-use termion::event::Key;
-use termion::input::TermRead;
+use termion::event::{self, Key};
+
+use crate::app::Message;
 
 pub enum Event<I> {
     Input(I),
     Tick,
+    /// Results of work done in the background, such as a refresh
+    Message(Message),
 }
 
 /// A small event handler that wrap termion input and tick events. Each event
 /// type is handled in its own thread and returned to a common `Receiver`
 pub struct Events {
     rx: mpsc::Receiver<Event<Key>>,
+    tx: mpsc::Sender<Event<Key>>,
     input_handle: thread::JoinHandle<()>,
     ignore_exit_key: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
     tick_handle: thread::JoinHandle<()>,
 }
 
@@ -47,13 +53,38 @@ impl Events {
     pub fn with_config(config: Config) -> Events {
         let (tx, rx) = mpsc::channel();
         let ignore_exit_key = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
         let input_handle = {
             let tx = tx.clone();
             let ignore_exit_key = ignore_exit_key.clone();
+            let paused = paused.clone();
             thread::spawn(move || {
-                let stdin = io::stdin();
-                for evt in stdin.keys() {
-                    if let Ok(key) = evt {
+                let mut tty = match termion::get_tty() {
+                    Ok(x) => x,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return;
+                    }
+                };
+
+                // Waiting for input with a timeout instead of blocking on a
+                // read, so that nothing is taken from the terminal while it's
+                // handed over to another program such as an editor
+                let mut buf = [0u8; 64];
+                loop {
+                    if paused.load(Ordering::Relaxed) {
+                        thread::sleep(config.tick_rate);
+                        continue;
+                    }
+                    if !input_ready(tty.as_raw_fd(), config.tick_rate) || paused.load(Ordering::Relaxed) {
+                        continue;
+                    }
+
+                    let n = match tty.read(&mut buf) {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => n
+                    };
+                    for key in parse_keys(&buf[..n]) {
                         if let Err(err) = tx.send(Event::Input(key)) {
                             eprintln!("{}", err);
                             return;
@@ -66,6 +97,7 @@ impl Events {
             })
         };
         let tick_handle = {
+            let tx = tx.clone();
             thread::spawn(move || loop {
                 if tx.send(Event::Tick).is_err() {
                     break;
@@ -75,7 +107,9 @@ impl Events {
         };
         Events {
             rx,
+            tx,
             ignore_exit_key,
+            paused,
             input_handle,
             tick_handle,
         }
@@ -85,6 +119,11 @@ impl Events {
         self.rx.recv()
     }
 
+    /// Sender for pushing events from background tasks into the same queue
+    pub fn sender(&self) -> mpsc::Sender<Event<Key>> {
+        self.tx.clone()
+    }
+
     pub fn disable_exit_key(&mut self) {
         self.ignore_exit_key.store(true, Ordering::Relaxed);
     }
@@ -92,4 +131,42 @@ impl Events {
     pub fn enable_exit_key(&mut self) {
         self.ignore_exit_key.store(false, Ordering::Relaxed);
     }
+
+    /// Stops reading input, leaving the terminal to another program
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Whether the terminal has input to read within `timeout`
+fn input_ready(fd: i32, timeout: Duration) -> bool {
+    let mut poll_fd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0
+    };
+    unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis() as libc::c_int) > 0 }
+}
+
+/// Keys in the bytes of a single read. An escape byte on its own is the
+/// escape key, while one followed by more bytes starts an escape sequence
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+
+    let mut bytes = bytes.iter().map(|byte| Ok(*byte));
+    while let Some(Ok(byte)) = bytes.next() {
+        if byte == b'\x1B' && bytes.len() == 0 {
+            keys.push(Key::Esc);
+            continue;
+        }
+        if let Ok(event::Event::Key(key)) = event::parse_event(byte, &mut bytes) {
+            keys.push(key);
+        }
+    }
+
+    keys
 }
@@ -1,10 +1,10 @@
 use crossclip::{Clipboard, SystemClipboard};
 
 pub mod event;
+pub mod prompt;
+pub mod editor;
 
 use tui::widgets::ListState;
-use webbrowser;
-use crate::hackernews::stories::Story;
 use crate::hackernews::comments::Comment;
 
 
@@ -31,7 +31,29 @@ impl<T> StatefulList<T> {
         list
     }
 
+    pub fn selected(&self) -> Option<&T> {
+        self.items.get(self.state.selected().unwrap_or_default())
+    }
+
+    /// Replaces the items while keeping the selection on the same item, as
+    /// identified by `key`, when it's still present
+    pub fn replace_items<K: PartialEq, F: Fn(&T) -> K>(&mut self, items: Vec<T>, key: F) {
+        let selected_key = self.selected().map(&key);
+        let selected = self.state.selected().unwrap_or_default();
+
+        self.items = items;
+
+        let selected = selected_key
+            .and_then(|k| self.items.iter().position(|item| key(item) == k))
+            .unwrap_or_else(|| selected.min(self.items.len().saturating_sub(1)));
+        self.state.select(Some(selected));
+    }
+
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -46,6 +68,10 @@ impl<T> StatefulList<T> {
     }
 
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -64,37 +90,17 @@ impl<T> StatefulList<T> {
     }
 
     pub fn go_to_bottom(&mut self) {
-        self.state.select(Some(self.items.len() - 1));
-    }
-}
-
-impl StatefulList<Story> {
-    pub fn select(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => i,
-            None => 0
-        };
-
-        let selected_items = &self.items[i];
-        webbrowser::open(&selected_items.url).unwrap();
-    }
-
-    pub fn get_comments(&mut self) -> Vec<i32>{
-        let i = match self.state.selected() {
-            Some(i) => i,
-            None => 0
-        };
+        if self.items.is_empty() {
+            return;
+        }
 
-        self.items[i].kids.to_vec()
+        self.state.select(Some(self.items.len() - 1));
     }
 }
 
 impl StatefulList<Comment> {
     pub fn next_parent(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => i,
-            None => 0,
-        };
+        let i = self.state.selected().unwrap_or_default();
 
         for (index, comment) in self.items.iter().enumerate() {
             if index > i && comment.depth == 0 {
@@ -105,15 +111,12 @@ impl StatefulList<Comment> {
     }
 
     pub fn previous_parent(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => i,
-            None => 0,
-        };
+        let i = self.state.selected().unwrap_or_default();
 
         for (index, comment) in self.items.iter().rev().enumerate() {
             // Creating inverse mimic original index with reversed array
             let inverse_index: i32 = (index as i32) - (self.items.len() - 1) as i32;
-            let inverse_index = inverse_index.abs() as usize;
+            let inverse_index = inverse_index.unsigned_abs() as usize;
 
             if inverse_index < i && comment.depth == 0 {
                 self.state.select(Some(inverse_index));
@@ -122,12 +125,104 @@ impl StatefulList<Comment> {
         }
     }
 
-    pub fn copy_text_to_clipboard(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => i,
-            None => 0
+    /// Index of the comment that the comment at `index` replies to, which is
+    /// always above it since replies are listed below their parent
+    fn parent_index(&self, index: usize) -> Option<usize> {
+        let parent = self.items.get(index)?.parent;
+        self.items[..index].iter().rposition(|comment| comment.id == parent)
+    }
+
+    pub fn go_to_parent(&mut self) {
+        let i = self.state.selected().unwrap_or_default();
+
+        if let Some(index) = self.parent_index(i) {
+            self.state.select(Some(index));
+        }
+    }
+
+    /// Moves to the next reply to the same parent, skipping over the replies
+    /// below the selected comment
+    pub fn next_sibling(&mut self) {
+        let i = self.state.selected().unwrap_or_default();
+        let selected = match self.items.get(i) {
+            Some(x) => x,
+            None => return
+        };
+
+        for (index, comment) in self.items.iter().enumerate().skip(i + 1) {
+            // Having left the parent's replies
+            if comment.depth < selected.depth {
+                break;
+            }
+            if comment.depth == selected.depth && comment.parent == selected.parent {
+                self.state.select(Some(index));
+                break;
+            }
+        }
+    }
+
+    pub fn previous_sibling(&mut self) {
+        let i = self.state.selected().unwrap_or_default();
+        let selected = match self.items.get(i) {
+            Some(x) => x,
+            None => return
         };
 
+        for (index, comment) in self.items[..i].iter().enumerate().rev() {
+            if comment.depth < selected.depth {
+                break;
+            }
+            if comment.depth == selected.depth && comment.parent == selected.parent {
+                self.state.select(Some(index));
+                break;
+            }
+        }
+    }
+
+    /// Moves to the top level comment that the selected comment's thread
+    /// started from
+    pub fn go_to_root(&mut self) {
+        let i = self.state.selected().unwrap_or_default();
+        let end = (i + 1).min(self.items.len());
+
+        if let Some(index) = self.items[..end].iter().rposition(|comment| comment.depth == 0) {
+            self.state.select(Some(index));
+        }
+    }
+
+    /// Comments that the selected comment is a reply to, starting from the
+    /// top level comment
+    pub fn ancestors(&self) -> Vec<&Comment> {
+        let mut ancestors = Vec::new();
+
+        let mut index = self.state.selected().unwrap_or_default();
+        while let Some(parent) = self.parent_index(index) {
+            ancestors.push(&self.items[parent]);
+            index = parent;
+        }
+        ancestors.reverse();
+
+        ancestors
+    }
+
+    /// Moves to the next comment by one of the given users, starting over
+    /// from the top after the last one. Returns whether there was one
+    pub fn next_by(&mut self, users: &[String]) -> bool {
+        let i = self.state.selected().unwrap_or_default();
+
+        let index = (1..=self.items.len())
+            .map(|offset| (i + offset) % self.items.len())
+            .find(|&index| users.contains(&self.items[index].by));
+        if let Some(index) = index {
+            self.state.select(Some(index));
+        }
+
+        index.is_some()
+    }
+
+    pub fn copy_text_to_clipboard(&mut self) {
+        let i = self.state.selected().unwrap_or_default();
+
         let comment = &self.items[i];
 
         let clipboard = SystemClipboard::new().unwrap();
@@ -0,0 +1,23 @@
+/// Single line text input shown at the bottom of the screen
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub label: String,
+    pub input: String,
+}
+
+impl Prompt {
+    pub fn new(label: &str, initial: &str) -> Prompt {
+        Prompt {
+            label: label.to_string(),
+            input: initial.to_string(),
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.input.push(c);
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+    }
+}